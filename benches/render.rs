@@ -0,0 +1,355 @@
+use std::hint::black_box;
+use std::sync::{Arc, Mutex};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use rast::graphics::*;
+
+const WIDTH: usize = 1280;
+const HEIGHT: usize = 720;
+
+struct BenchShader {
+    // nothing
+}
+
+struct BenchUniforms {
+    vertices: Vec<Point3<f32>>,
+    color: u32,
+}
+
+impl Shader for BenchShader {
+    type Uniform = BenchUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.color
+    }
+}
+
+// transforms every vertex by view_projection * model, either multiplied out per vertex or once per
+// instance in prepare_instance
+struct InstancedShader {
+    hoist: bool,
+}
+
+struct InstancedUniforms {
+    vertices: Vec<Point3<f32>>,
+    view_projection: Matrix4<f32>,
+    models: Vec<Matrix4<f32>>,
+}
+
+impl InstancedShader {
+    fn transform(data: &InstancedUniforms, instance_id: usize) -> Matrix4<f32> {
+        data.view_projection * data.models[instance_id]
+    }
+}
+
+impl Shader for InstancedShader {
+    type Uniform = InstancedUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let transform = match context.instance.transform {
+            Some(transform) => transform,
+            None => Self::transform(context.data, context.instance_id),
+        };
+
+        let clip = transform * context.data.vertices[context.vertex_id].to_homogeneous();
+        VertexOutput {
+            position: Point3::from_homogeneous(clip).unwrap(),
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, _context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        0x3080C0FF
+    }
+
+    fn prepare_instance(&self, data: &Self::Uniform, instance_id: usize) -> InstanceCache {
+        InstanceCache {
+            transform: self.hoist.then(|| Self::transform(data, instance_id)),
+        }
+    }
+}
+
+fn create_target() -> Arc<Mutex<Framebuffer>> {
+    Arc::new(Mutex::new(Framebuffer::new(WIDTH, HEIGHT, 1, true)))
+}
+
+fn clear_target(target: &Arc<Mutex<Framebuffer>>) {
+    target
+        .lock()
+        .unwrap()
+        .clear(&ClearValue {
+            color: 0x000000FF,
+            depth: 1.0,
+        })
+        .unwrap();
+}
+
+// a single triangle covering the whole framebuffer
+fn fullscreen_triangle(color: u32) -> (BenchUniforms, Vec<u16>) {
+    let uniforms = BenchUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.5),
+            Point3::new(3.0, -1.0, 0.5),
+            Point3::new(-1.0, 3.0, 0.5),
+        ],
+        color,
+    };
+
+    (uniforms, vec![0, 1, 2])
+}
+
+// a grid of quads split into two triangles each, 100x50 quads for 10k triangles
+fn grid_mesh(columns: usize, rows: usize) -> (BenchUniforms, Vec<u16>) {
+    let mut vertices = Vec::with_capacity((columns + 1) * (rows + 1));
+    for y in 0..=rows {
+        for x in 0..=columns {
+            let ndc_x = x as f32 / columns as f32 * 2.0 - 1.0;
+            let ndc_y = y as f32 / rows as f32 * 2.0 - 1.0;
+
+            vertices.push(Point3::new(ndc_x, ndc_y, 0.5));
+        }
+    }
+
+    let mut indices = Vec::with_capacity(columns * rows * 6);
+    for y in 0..rows {
+        for x in 0..columns {
+            let top_left = (y * (columns + 1) + x) as u16;
+            let top_right = top_left + 1;
+            let bottom_left = top_left + columns as u16 + 1;
+            let bottom_right = bottom_left + 1;
+
+            indices.extend([top_left, top_right, bottom_left]);
+            indices.extend([top_right, bottom_right, bottom_left]);
+        }
+    }
+
+    let uniforms = BenchUniforms {
+        vertices,
+        color: 0x3080C0FF,
+    };
+
+    (uniforms, indices)
+}
+
+fn render(
+    rast: &mut Rasterizer,
+    pipeline: &Pipeline<BenchShader>,
+    indices: &[u16],
+    uniforms: &BenchUniforms,
+) {
+    let call = IndexedRenderCall::builder(pipeline, indices, uniforms).build();
+    rast.render_indexed(black_box(&call)).unwrap();
+}
+
+fn bench_clear(c: &mut Criterion) {
+    let target = create_target();
+
+    c.bench_function("clear", |b| b.iter(|| clear_target(black_box(&target))));
+}
+
+fn bench_fill(c: &mut Criterion) {
+    let target = create_target();
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(target.clone()).unwrap();
+
+    let pipeline = Pipeline::builder(BenchShader {})
+        .depth(DepthMode::Write)
+        .build()
+        .unwrap();
+
+    let (uniforms, indices) = fullscreen_triangle(0xFF0000FF);
+    c.bench_function("fullscreen triangle", |b| {
+        b.iter(|| {
+            clear_target(&target);
+            render(&mut rast, &pipeline, &indices, &uniforms);
+        })
+    });
+
+    let (uniforms, indices) = grid_mesh(100, 50);
+    c.bench_function("10k triangle mesh", |b| {
+        b.iter(|| {
+            clear_target(&target);
+            render(&mut rast, &pipeline, &indices, &uniforms);
+        })
+    });
+}
+
+fn bench_blending(c: &mut Criterion) {
+    let target = create_target();
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(target.clone()).unwrap();
+
+    let opaque = Pipeline::builder(BenchShader {}).build().unwrap();
+    let blended = Pipeline::builder(BenchShader {})
+        .blending(vec![BlendAttachment {
+            color: Some(ComponentBlendOp {
+                src_factor: BlendFactor::SrcAlpha,
+                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                op: BlendOp::Add,
+            }),
+            alpha: None,
+        }])
+        .color_attachments(1)
+        .build()
+        .unwrap();
+
+    let (uniforms, indices) = fullscreen_triangle(0xFF000080);
+
+    let mut group = c.benchmark_group("blending");
+    group.bench_function("off", |b| {
+        b.iter(|| render(&mut rast, &opaque, &indices, &uniforms))
+    });
+
+    group.bench_function("on", |b| {
+        b.iter(|| render(&mut rast, &blended, &indices, &uniforms))
+    });
+
+    group.finish();
+}
+
+// a framebuffer only 16 pixels wide, so every row is a single cache line and handing out one row
+// per task makes neighbouring threads write to the same lines
+fn bench_granularity(c: &mut Criterion) {
+    let target = Arc::new(Mutex::new(Framebuffer::new(16, 16384, 1, true)));
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(target.clone()).unwrap();
+
+    let pipeline = Pipeline::builder(BenchShader {}).build().unwrap();
+    let (uniforms, indices) = fullscreen_triangle(0xFF0000FF);
+
+    let mut group = c.benchmark_group("narrow framebuffer");
+    for (name, granularity) in [("1 row per task", Some(1)), ("auto", None)] {
+        rast.set_scanline_granularity(granularity);
+        group.bench_function(name, |b| {
+            b.iter(|| render(&mut rast, &pipeline, &indices, &uniforms))
+        });
+    }
+
+    group.finish();
+}
+
+// 16 small instances of the 10k triangle mesh, so most of the time goes into vertex shading
+fn bench_instance_transform(c: &mut Criterion) {
+    let target = create_target();
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(target.clone()).unwrap();
+
+    let (mesh, indices) = grid_mesh(100, 50);
+    let uniforms = InstancedUniforms {
+        vertices: mesh.vertices,
+        view_projection: Matrix4::new_scaling(0.25),
+        models: (0..16)
+            .map(|i| {
+                let offset = Vector3::new((i % 4) as f32 - 1.5, (i / 4) as f32 - 1.5, 0.0);
+                Matrix4::new_translation(&offset)
+            })
+            .collect(),
+    };
+
+    let mut group = c.benchmark_group("instance transform");
+    for (name, hoist) in [("per vertex", false), ("prepare_instance", true)] {
+        let pipeline = Pipeline::builder(InstancedShader { hoist })
+            .build()
+            .unwrap();
+        let call = IndexedRenderCall::builder(&pipeline, &indices, &uniforms)
+            .instances(uniforms.models.len())
+            .build();
+
+        group.bench_function(name, |b| {
+            b.iter(|| rast.render_indexed(black_box(&call)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+// visits every row of the framebuffer, either collected into a Vec up front or one at a time
+fn bench_scanlines(c: &mut Criterion) {
+    let target = create_target();
+    let mut framebuffer = target.lock().unwrap();
+
+    let mut group = c.benchmark_group("scanlines");
+    group.bench_function("collected", |b| {
+        b.iter(|| {
+            for scanline in framebuffer.scanlines(0, HEIGHT) {
+                black_box(scanline.color[0][0]);
+            }
+        })
+    });
+
+    group.bench_function("iterator", |b| {
+        b.iter(|| {
+            for scanline in framebuffer.scanlines_iter(0, HEIGHT) {
+                black_box(scanline.color[0][0]);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+// 64k triangles only a few pixels across, so the per face overhead outweighs shading
+fn bench_small_faces(c: &mut Criterion) {
+    let target = create_target();
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(target.clone()).unwrap();
+
+    let pipeline = Pipeline::builder(BenchShader {}).build().unwrap();
+    let (uniforms, indices) = grid_mesh(256, 128);
+
+    c.bench_function("64k small triangles", |b| {
+        b.iter(|| render(&mut rast, &pipeline, &indices, &uniforms))
+    });
+}
+
+// tuples blend element by element, and the slice version collects every element into a Vec first
+fn bench_working_blend(c: &mut Criterion) {
+    let data: [(Point3<f32>, u32); 3] = [
+        (Point3::new(0.0, 0.0, 0.0), 0xFF0000FF),
+        (Point3::new(1.0, 0.0, 0.0), 0x00FF00FF),
+        (Point3::new(0.0, 1.0, 0.0), 0x0000FFFF),
+    ];
+
+    let [first, second, third] = &data;
+    let weights = [0.2, 0.3, 0.5];
+
+    let mut group = c.benchmark_group("working blend");
+    group.bench_function("slice", |b| {
+        b.iter(|| {
+            <(Point3<f32>, u32)>::blend(black_box(&[first, second, third]), black_box(&weights))
+        })
+    });
+
+    group.bench_function("blend3", |b| {
+        b.iter(|| {
+            let [wa, wb, wc] = black_box(weights);
+            <(Point3<f32>, u32)>::blend3(first, wa, second, wb, third, wc)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_clear,
+    bench_fill,
+    bench_blending,
+    bench_granularity,
+    bench_instance_transform,
+    bench_scanlines,
+    bench_small_faces,
+    bench_working_blend
+);
+criterion_main!(benches);
@@ -27,11 +27,17 @@ impl Blendable for TestWorking {
             ),
         }
     }
+
+    fn blend3(a: &Self, wa: f32, b: &Self, wb: f32, c: &Self, wc: f32) -> Self {
+        TestWorking {
+            color: u32::blend3(&a.color, wa, &b.color, wb, &c.color, wc),
+        }
+    }
 }
 
 struct TestUniformData {
     model: Matrix4<f32>,
-    vertices: Box<[Vertex]>,
+    vertices: VertexBuffer<Vertex>,
 }
 
 impl Shader for TestShader {
@@ -39,7 +45,7 @@ impl Shader for TestShader {
     type Working = TestWorking;
 
     fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
-        let vertex = &context.data.vertices[context.vertex_id];
+        let vertex = context.data.vertices.get(context.vertex_id).unwrap();
 
         let homogenous = vertex.position.to_homogeneous();
         let world_position = context.data.model * homogenous;
@@ -57,88 +63,153 @@ impl Shader for TestShader {
     }
 }
 
-fn dump_image(data: &Image<u32>) {
-    let (width, height) = data.size();
-    let mut image = bmp::Image::new(width as u32, height as u32);
+// bmp has no alpha channel, so transparent pixels are composited over the background. size is
+// the expected size of the dump, which has to match the image
+fn dump_image(
+    data: &Image<u32>,
+    size: (usize, usize),
+    background: RGBA8,
+) -> Result<bmp::Image, Box<dyn Error>> {
+    if data.size() != size {
+        return Err(format!(
+            "Image is {}x{} but the dump is {}x{}!",
+            data.size().0,
+            data.size().1,
+            size.0,
+            size.1
+        )
+        .into());
+    }
+
+    let mut image = bmp::Image::new(size.0 as u32, size.1 as u32);
 
+    // the sizes match, so every pixel is in bounds
     for (x, y) in image.coordinates() {
-        let color = data.at(x as usize, y as usize).unwrap_or(&0).to_be_bytes();
+        let pixel = data.at(x as usize, y as usize).unwrap();
+        let color = RGBA8::from(*pixel).composite_over(background);
 
         image.set_pixel(
             x,
             y,
             bmp::Pixel {
-                r: color[0],
-                g: color[1],
-                b: color[2],
+                r: color.r,
+                g: color.g,
+                b: color.b,
             },
         );
     }
 
-    image.save("dump.bmp").unwrap();
+    Ok(image)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let mut rast = Rasterizer::new();
-    let arc = Arc::new(Mutex::new(Framebuffer::new(1600, 900, 1, true)));
+    let arc = Arc::new(Mutex::new(Framebuffer::from_desc(&FramebufferDesc {
+        depth: true,
+        ..FramebufferDesc::new(1600, 900)
+    })?));
 
     {
         let mut fb = arc.lock().unwrap();
         fb.clear(&ClearValue {
             color: 0x787878FF,
             depth: 1.0,
-        });
+        })?;
     }
 
     println!("Cleared");
 
-    rast.push_render_target(arc.clone());
-    rast.render_indexed(&IndexedRenderCall {
-        pipeline: &Pipeline {
-            depth: DepthMode::Write,
-            cull_back: true,
-            winding_order: WindingOrder::CounterClockwise,
-            blending: None,
-            shader: TestShader {},
-        },
-        vertex_offset: 0,
-        first_instance: 0,
-        instance_count: 1,
-        scissor: None,
-        indices: &[0, 2, 1],
-        data: &TestUniformData {
-            model: Matrix4::new_translation(&Vector3::new(0.0, 0.0, 0.5)),
-            vertices: Box::new([
-                Vertex {
-                    position: Point3::new(0.0, -0.5, 0.0),
-                    color: 0xFF0000FF,
-                },
-                Vertex {
-                    position: Point3::new(0.5, 0.5, 0.0),
-                    color: 0x00FF00FF,
-                },
-                Vertex {
-                    position: Point3::new(-0.5, 0.5, 0.0),
-                    color: 0x0000FFFF,
-                },
-            ]),
-        },
-    })?;
+    rast.push_render_target(arc.clone())?;
+    let pipeline = Pipeline {
+        topology: Topology::TriangleList,
+        depth: DepthMode::Write,
+        depth_compare: DepthCompare::LessOrEqual,
+        cull_back: true,
+        winding_order: WindingOrder::CounterClockwise,
+        blending: None,
+        min_triangle_area: None,
+        clamp_barycentric: false,
+        color_key: None,
+        point_size: 1.0,
+        read_dst: false,
+        alpha_zero_discard: AlphaZeroDiscard::Off,
+        line_rasterization: LineRasterization::Aliased,
+        tessellation_level: 1,
+        shader: TestShader {},
+    };
+
+    let data = TestUniformData {
+        model: Matrix4::new_translation(&Vector3::new(0.0, 0.0, 0.5)),
+        vertices: VertexBuffer::new(vec![
+            Vertex {
+                position: Point3::new(0.0, -0.5, 0.0),
+                color: 0xFF0000FF,
+            },
+            Vertex {
+                position: Point3::new(0.5, 0.5, 0.0),
+                color: 0x00FF00FF,
+            },
+            Vertex {
+                position: Point3::new(-0.5, 0.5, 0.0),
+                color: 0x0000FFFF,
+            },
+        ]),
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 2, 1], &data)
+        .vertex_count(data.vertices.len())
+        .build();
+
+    rast.render_indexed(&call)?;
 
     rast.pop_render_target()?;
     println!("Rendered");
 
-    let stats = rast.stats();
-    println!("{} calls", stats.calls);
-    println!("{} instances", stats.instances);
-    println!("{} faces processed", stats.faces_processed);
-    println!("{} faces rendered", stats.faces_rendered);
+    println!("{}", rast.stats());
 
     {
         let fb = arc.lock().unwrap();
-        dump_image(&fb.color_attachments()[0]);
+        let image = dump_image(
+            &fb.color_attachments()[0],
+            fb.size(),
+            RGBA8::from(0x000000FF),
+        )?;
+
+        image.save("dump.bmp")?;
     }
 
     println!("Dumped image");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_matches_the_image() {
+        let mut data = Image::new(3, 2);
+        data.exchange(0, 0, 0xFF000080);
+        data.exchange(2, 1, 0x00FF00FF);
+
+        let image = dump_image(&data, (3, 2), RGBA8::from(0x0000FFFF)).unwrap();
+        assert_eq!((image.get_width(), image.get_height()), (3, 2));
+        assert_eq!(image.get_pixel(2, 1), bmp::Pixel { r: 0, g: 255, b: 0 });
+
+        // fully transparent pixels show the background, half transparent ones are mixed with it
+        assert_eq!(image.get_pixel(1, 0), bmp::Pixel { r: 0, g: 0, b: 255 });
+
+        let mixed = image.get_pixel(0, 0);
+        assert!(mixed.r.abs_diff(128) <= 1 && mixed.b.abs_diff(127) <= 1 && mixed.g == 0);
+    }
+
+    #[test]
+    fn dump_of_the_wrong_size_is_an_error() {
+        let data = Image::new(3, 2);
+        let Err(error) = dump_image(&data, (4, 2), RGBA8::from(0x000000FF)) else {
+            panic!("Mismatched sizes were dumped!");
+        };
+
+        assert_eq!(error.to_string(), "Image is 3x2 but the dump is 4x2!");
+    }
+}
@@ -9,8 +9,9 @@ use nalgebra::{Matrix4, Point3, Vector3};
 use rand::prelude::*;
 
 use rast::graphics::{
-    Blendable, ClearValue, DepthMode, FragmentContext, Framebuffer, IndexedRenderCall, Pipeline,
-    Rasterizer, Shader, VertexContext, VertexOutput, WindingOrder,
+    AlphaZeroDiscard, Blendable, ClearValue, DepthCompare, DepthMode, FragmentContext, Framebuffer,
+    IndexedRenderCall, LineRasterization, Pipeline, Rasterizer, Shader, Topology, VertexContext,
+    VertexOutput, WindingOrder,
 };
 
 use winit::application::ApplicationHandler;
@@ -102,6 +103,12 @@ impl GraphicsContext {
 
     fn update_context(&mut self) -> Result<(), Box<dyn Error>> {
         let size = self.window.inner_size();
+
+        // minimized windows report 0x0, keep the old framebuffer until it comes back
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+
         self.surface.resize(
             NonZeroU32::new(size.width).unwrap(),
             NonZeroU32::new(size.height).unwrap(),
@@ -109,8 +116,8 @@ impl GraphicsContext {
 
         let (fb_width, fb_height) = self.current_fb_size();
         if size.width as usize != fb_width || size.height as usize != fb_height {
-            let new_fb = Self::create_framebuffer(&self.window);
-            self.framebuffer = Arc::new(Mutex::new(new_fb));
+            let mut fb = self.framebuffer.lock().unwrap();
+            fb.resize(size.width as usize, size.height as usize, true);
         }
 
         Ok(())
@@ -202,29 +209,29 @@ impl App {
         }
     }
 
-    fn clear_framebuffer(graphics: &mut GraphicsContext) {
+    fn clear_framebuffer(graphics: &mut GraphicsContext) -> Result<(), Box<dyn Error>> {
         let mut fb = graphics.framebuffer.lock().unwrap();
         fb.clear(&ClearValue {
             color: 0x787878FF,
             depth: 1.0,
-        });
+        })?;
+
+        Ok(())
     }
 
     fn render(graphics: &mut GraphicsContext, data: &AppData) -> Result<(), Box<dyn Error>> {
-        Self::clear_framebuffer(graphics);
+        Self::clear_framebuffer(graphics)?;
 
         graphics.rast.new_frame()?;
-        graphics.rast.push_render_target(graphics.framebuffer.clone());
-
-        graphics.rast.render_indexed(&IndexedRenderCall {
-            pipeline: &data.pipeline,
-            vertex_offset: 0,
-            first_instance: 0,
-            instance_count: data.uniforms.instances.len(),
-            scissor: None,
-            indices: &data.indices,
-            data: &data.uniforms,
-        })?;
+        graphics
+            .rast
+            .push_render_target(graphics.framebuffer.clone())?;
+
+        let call = IndexedRenderCall::builder(&data.pipeline, &data.indices, &data.uniforms)
+            .instances(data.uniforms.instances.len())
+            .build();
+
+        graphics.rast.render_indexed(&call)?;
 
         graphics.rast.pop_render_target()?;
         Ok(())
@@ -304,10 +311,20 @@ fn main() -> Result<(), Box<dyn Error>> {
 
         data: AppData {
             pipeline: Pipeline {
+                topology: Topology::TriangleList,
                 depth: DepthMode::Write,
+                depth_compare: DepthCompare::LessOrEqual,
                 cull_back: false,
                 winding_order: WindingOrder::Clockwise,
                 blending: None,
+                min_triangle_area: None,
+                clamp_barycentric: false,
+                color_key: None,
+                point_size: 1.0,
+                read_dst: false,
+                alpha_zero_discard: AlphaZeroDiscard::Off,
+                line_rasterization: LineRasterization::Aliased,
+                tessellation_level: 1,
                 shader: AppShader {},
             },
             uniforms: AppUniforms {
@@ -1,9 +1,20 @@
-use std::array;
-
 use nalgebra::{Point, SMatrix, Vector4};
 
+use super::color::{pack_channels, unpack_channels};
+
+// data and weights always have the same length, and the weights are finite and sum to 1. the
+// rasterizer checks this in debug builds before calling blend
 pub trait Blendable {
     fn blend(data: &[&Self], weights: &[f32]) -> Self;
+
+    // what the rasterizer calls for triangles. override it if blend has to allocate, e.g. to
+    // collect a field out of every element of data
+    fn blend3(a: &Self, wa: f32, b: &Self, wb: f32, c: &Self, wc: f32) -> Self
+    where
+        Self: Sized,
+    {
+        Self::blend(&[a, b, c], &[wa, wb, wc])
+    }
 }
 
 impl Blendable for u32 {
@@ -11,14 +22,11 @@ impl Blendable for u32 {
         let mut result = Vector4::zeros();
 
         for i in 0..data.len() {
-            let channels = Vector4::from_column_slice(
-                &data[i].to_be_bytes().map(|channel| channel as f32 / 256.0),
-            );
-
+            let channels = Vector4::from(unpack_channels(*data[i]));
             result += channels * weights[i];
         }
 
-        u32::from_be_bytes(array::from_fn(|i| (result[i] * 256.0) as u8))
+        pack_channels(result.into())
     }
 }
 
@@ -57,3 +65,37 @@ impl<const D: usize> Blendable for Point<f32, D> {
         result
     }
 }
+
+// blends one field of every element without collecting the field references onto the heap. the
+// rasterizer and sampler only ever blend up to 4 elements at once
+fn blend_field<T, F: Blendable>(data: &[&T], weights: &[f32], field: impl Fn(&T) -> &F) -> F {
+    match data {
+        [a] => F::blend(&[field(a)], weights),
+        [a, b] => F::blend(&[field(a), field(b)], weights),
+        [a, b, c] => F::blend3(field(a), weights[0], field(b), weights[1], field(c), weights[2]),
+        [a, b, c, d] => F::blend(&[field(a), field(b), field(c), field(d)], weights),
+        _ => F::blend(
+            &data.iter().map(|element| field(element)).collect::<Vec<_>>(),
+            weights,
+        ),
+    }
+}
+
+// tuples blend element by element
+macro_rules! impl_blendable_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Blendable),+> Blendable for ($($name,)+) {
+            fn blend(data: &[&Self], weights: &[f32]) -> Self {
+                ($(blend_field(data, weights, |tuple| &tuple.$index),)+)
+            }
+
+            fn blend3(a: &Self, wa: f32, b: &Self, wb: f32, c: &Self, wc: f32) -> Self {
+                ($($name::blend3(&a.$index, wa, &b.$index, wb, &c.$index, wc),)+)
+            }
+        }
+    };
+}
+
+impl_blendable_tuple!(A 0, B 1);
+impl_blendable_tuple!(A 0, B 1, C 2);
+impl_blendable_tuple!(A 0, B 1, C 2, D 3);
@@ -0,0 +1,38 @@
+use nalgebra::{Matrix4, Point3, Vector3, Vector4};
+
+// the rasterizer expects vertex positions with x and y in NDC, +Y pointing down the screen, and z
+// as the positive distance from the camera, which it interpolates perspective correctly. the
+// projections below produce clip space positions that clip_to_vertex_position turns into exactly
+// that, with the Y flip built in so +Y stays up in world space.
+//
+// faces keep their visual winding on screen, so meshes following the right-handed convention
+// (front faces counterclockwise) cull with WindingOrder::CounterClockwise, and left-handed meshes
+// (front faces clockwise) cull with WindingOrder::Clockwise
+
+fn flip_y() -> Matrix4<f32> {
+    Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, -1.0, 1.0))
+}
+
+// projection for a right-handed view space, with the camera looking down -Z
+pub fn perspective_rh(aspect: f32, fovy: f32, znear: f32, zfar: f32) -> Matrix4<f32> {
+    flip_y() * Matrix4::new_perspective(aspect, fovy, znear, zfar)
+}
+
+// projection for a left-handed view space, with the camera looking down +Z
+pub fn perspective_lh(aspect: f32, fovy: f32, znear: f32, zfar: f32) -> Matrix4<f32> {
+    let flip_z = Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, -1.0));
+    perspective_rh(aspect, fovy, znear, zfar) * flip_z
+}
+
+pub fn look_at_rh(eye: &Point3<f32>, target: &Point3<f32>, up: &Vector3<f32>) -> Matrix4<f32> {
+    Matrix4::look_at_rh(eye, target, up)
+}
+
+pub fn look_at_lh(eye: &Point3<f32>, target: &Point3<f32>, up: &Vector3<f32>) -> Matrix4<f32> {
+    Matrix4::look_at_lh(eye, target, up)
+}
+
+// divides x and y by w and keeps w as the depth, for use as VertexOutput::position
+pub fn clip_to_vertex_position(clip: &Vector4<f32>) -> Point3<f32> {
+    Point3::new(clip.x / clip.w, clip.y / clip.w, clip.w)
+}
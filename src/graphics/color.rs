@@ -0,0 +1,157 @@
+// 8 bits per channel color, matching the 0xRRGGBBAA layout of color attachments
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RGBA8 {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl From<u32> for RGBA8 {
+    fn from(color: u32) -> RGBA8 {
+        let [r, g, b, a] = color.to_be_bytes();
+        RGBA8 { r, g, b, a }
+    }
+}
+
+impl From<RGBA8> for u32 {
+    fn from(color: RGBA8) -> u32 {
+        color.to_u32()
+    }
+}
+
+impl RGBA8 {
+    pub fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> RGBA8 {
+        RGBA8 { r, g, b, a }
+    }
+
+    pub fn to_u32(&self) -> u32 {
+        u32::from_be_bytes([self.r, self.g, self.b, self.a])
+    }
+
+    pub fn r(&self) -> u8 {
+        self.r
+    }
+
+    pub fn g(&self) -> u8 {
+        self.g
+    }
+
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+
+    pub fn a(&self) -> u8 {
+        self.a
+    }
+
+    // standard "over" operator with straight (non-premultiplied) alpha
+    pub fn composite_over(&self, bg: RGBA8) -> RGBA8 {
+        let src_alpha = self.a as f32 / 255.0;
+        let dst_alpha = bg.a as f32 / 255.0 * (1.0 - src_alpha);
+        let alpha = src_alpha + dst_alpha;
+
+        if alpha <= 0.0 {
+            return RGBA8::default();
+        }
+
+        let channel = |src: u8, dst: u8| {
+            let value = (src as f32 * src_alpha + dst as f32 * dst_alpha) / alpha;
+            value.round() as u8
+        };
+
+        RGBA8 {
+            r: channel(self.r, bg.r),
+            g: channel(self.g, bg.g),
+            b: channel(self.b, bg.b),
+            a: (alpha * 255.0).round() as u8,
+        }
+    }
+}
+
+// splits a 0xRRGGBBAA color into rgba channels in [0, 1]. 255 maps to exactly 1.0, so opaque
+// colors stay opaque through blending
+pub fn unpack_channels(color: u32) -> [f32; 4] {
+    color.to_be_bytes().map(|c| (c as f32) / 255.0)
+}
+
+// undoes the srgb transfer function on a channel in [0, 1]
+pub fn srgb_to_linear(c: f32) -> f32 {
+    match c <= 0.04045 {
+        true => c / 12.92,
+        false => ((c + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+// clamps a channel to [0, 1]. blending can push channels out of range, e.g. adding two bright
+// colors, and nan from bad interpolation weights would otherwise only become 0 through the cast
+fn saturate(c: f32) -> f32 {
+    match c.is_nan() {
+        true => 0.0,
+        false => c.clamp(0.0, 1.0),
+    }
+}
+
+// inverse of unpack_channels. channels are saturated and rounded to the nearest step
+pub fn pack_channels(channels: [f32; 4]) -> u32 {
+    u32::from_be_bytes(channels.map(|c| (saturate(c) * 255.0).round() as u8))
+}
+
+// how a u32 color attachment packs its channels. fragment outputs and clear colors are expected
+// in the format of the framebuffer they're written to, and blending works on the decoded channels
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    // 0xRRGGBBAA, 8 bits per channel
+    #[default]
+    Rgba8,
+
+    // 10 bits each for red, green and blue from the top down, then 2 bits of alpha
+    Rgb10A2,
+}
+
+impl PixelFormat {
+    // channels normalized to [0, 1] in rgba order
+    pub fn decode(&self, color: u32) -> [f32; 4] {
+        match self {
+            PixelFormat::Rgba8 => unpack_channels(color),
+            PixelFormat::Rgb10A2 => [
+                ((color >> 22) & 0x3FF) as f32 / 1023.0,
+                ((color >> 12) & 0x3FF) as f32 / 1023.0,
+                ((color >> 2) & 0x3FF) as f32 / 1023.0,
+                (color & 0x3) as f32 / 3.0,
+            ],
+        }
+    }
+
+    // channels are clamped to [0, 1], with nan as 0, and rounded to the nearest representable value
+    pub fn encode(&self, channels: [f32; 4]) -> u32 {
+        let quantize = |c: f32, max: u32| (saturate(c) * max as f32).round() as u32;
+
+        match self {
+            PixelFormat::Rgba8 => pack_channels(channels),
+            PixelFormat::Rgb10A2 => {
+                let [r, g, b, a] = channels;
+                (quantize(r, 0x3FF) << 22)
+                    | (quantize(g, 0x3FF) << 12)
+                    | (quantize(b, 0x3FF) << 2)
+                    | quantize(a, 0x3)
+            }
+        }
+    }
+
+    // repacks a color from this format into another, e.g. for shaders written against rgba8
+    pub fn convert(&self, color: u32, target: PixelFormat) -> u32 {
+        match *self == target {
+            true => color,
+            false => target.encode(self.decode(color)),
+        }
+    }
+
+    // the color with its alpha bits zeroed
+    pub fn rgb_bits(&self, color: u32) -> u32 {
+        match self {
+            PixelFormat::Rgba8 => color & !0xFF,
+            PixelFormat::Rgb10A2 => color & !0x3,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::array;
+use std::sync::{Arc, Mutex};
+
+use nalgebra::{Matrix4, Point2, Point3, Vector3};
+
+use super::camera::{look_at_rh, perspective_rh};
+use super::framebuffer::{ClearValue, Framebuffer, FramebufferError};
+use super::rasterizer::{Rasterizer, RasterizerError};
+use super::sampler::{Filter, SamplerState, WrapMode, sample_image};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    // in the order faces are stored and rendered
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        match self {
+            CubeFace::PositiveX => Vector3::x(),
+            CubeFace::NegativeX => -Vector3::x(),
+            CubeFace::PositiveY => Vector3::y(),
+            CubeFace::NegativeY => -Vector3::y(),
+            CubeFace::PositiveZ => Vector3::z(),
+            CubeFace::NegativeZ => -Vector3::z(),
+        }
+    }
+
+    // world space direction pointing up the rendered face
+    pub fn up(&self) -> Vector3<f32> {
+        match self {
+            CubeFace::PositiveY => Vector3::z(),
+            CubeFace::NegativeY => -Vector3::z(),
+            _ => Vector3::y(),
+        }
+    }
+
+    // the face whose frustum contains the direction, picked by its largest component
+    pub fn from_direction(dir: &Vector3<f32>) -> CubeFace {
+        let abs = dir.abs();
+
+        if abs.x >= abs.y && abs.x >= abs.z {
+            match dir.x >= 0.0 {
+                true => CubeFace::PositiveX,
+                false => CubeFace::NegativeX,
+            }
+        } else if abs.y >= abs.z {
+            match dir.y >= 0.0 {
+                true => CubeFace::PositiveY,
+                false => CubeFace::NegativeY,
+            }
+        } else {
+            match dir.z >= 0.0 {
+                true => CubeFace::PositiveZ,
+                false => CubeFace::NegativeZ,
+            }
+        }
+    }
+
+    // right-handed view matrix for a camera at position looking through this face
+    pub fn view(&self, position: &Point3<f32>) -> Matrix4<f32> {
+        look_at_rh(position, &(position + self.forward()), &self.up())
+    }
+
+    // where a direction lands on this face, with (0, 0) at the top left. matches the projection
+    // used by CubeMap::render
+    fn uv(&self, dir: &Vector3<f32>) -> Point2<f32> {
+        let forward = self.forward();
+        let right = forward.cross(&self.up());
+        let depth = dir.dot(&forward);
+
+        Point2::new(
+            (dir.dot(&right) / depth + 1.0) / 2.0,
+            (1.0 - dir.dot(&self.up()) / depth) / 2.0,
+        )
+    }
+}
+
+// six square framebuffers, each with a single color attachment and a depth attachment
+pub struct CubeMap {
+    size: usize,
+    faces: [Arc<Mutex<Framebuffer>>; 6],
+}
+
+impl CubeMap {
+    pub fn new(size: usize) -> CubeMap {
+        CubeMap {
+            size,
+            faces: array::from_fn(|_| Arc::new(Mutex::new(Framebuffer::new(size, size, 1, true)))),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn face(&self, face: CubeFace) -> &Arc<Mutex<Framebuffer>> {
+        &self.faces[face.index()]
+    }
+
+    pub fn clear(&self, value: &ClearValue) -> Result<(), FramebufferError> {
+        for face in &self.faces {
+            face.lock().unwrap().clear(value)?;
+        }
+
+        Ok(())
+    }
+
+    // 90 degree right-handed projection shared by every face
+    pub fn projection(znear: f32, zfar: f32) -> Matrix4<f32> {
+        perspective_rh(1.0, std::f32::consts::FRAC_PI_2, znear, zfar)
+    }
+
+    // pushes each face as the render target in turn and calls draw with it and its view
+    // projection matrix for a camera at position. faces are not cleared beforehand
+    pub fn render<F>(
+        &self,
+        rast: &mut Rasterizer,
+        position: &Point3<f32>,
+        znear: f32,
+        zfar: f32,
+        mut draw: F,
+    ) -> Result<(), RasterizerError>
+    where
+        F: FnMut(&mut Rasterizer, CubeFace, &Matrix4<f32>) -> Result<(), RasterizerError>,
+    {
+        let projection = Self::projection(znear, zfar);
+
+        for face in CubeFace::ALL {
+            let view_projection = projection * face.view(position);
+
+            rast.push_render_target(self.face(face).clone())?;
+            let result = draw(rast, face, &view_projection);
+            rast.pop_render_target()?;
+
+            result?;
+        }
+
+        Ok(())
+    }
+
+    // nearest texel in the given direction from the center of the cube
+    pub fn sample(&self, dir: Vector3<f32>) -> u32 {
+        let face = CubeFace::from_direction(&dir);
+        let framebuffer = self.face(face).lock().unwrap();
+
+        let sampler = SamplerState {
+            filter: Filter::Nearest,
+            wrap_u: WrapMode::ClampToEdge,
+            wrap_v: WrapMode::ClampToEdge,
+            ..Default::default()
+        };
+
+        sample_image(&framebuffer.color_attachments()[0], &sampler, face.uv(&dir))
+    }
+}
@@ -0,0 +1,202 @@
+use std::any::Any;
+
+use nalgebra::{Point2, Point3};
+
+use super::blending::Blendable;
+use super::rasterizer::Pipeline;
+use super::shader::{
+    FragmentContext, InstanceBounds, InstanceCache, Shader, VertexContext, VertexOutput,
+};
+
+// type erased shaders, so the shader of a pipeline can be swapped at runtime without changing
+// the pipeline's type. every vertex output is boxed, blending goes through a vtable and uniforms
+// and working data are downcast on every call, so expect these to be noticeably slower than a
+// statically dispatched Shader. meant for live editing, not for shipping
+
+pub type DynUniform = Box<dyn Any + Send + Sync>;
+pub type DynPipeline = Pipeline<Box<dyn DynShader>>;
+
+// object safe counterpart of Blendable, implemented for every blendable type
+pub trait DynWorking: Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    // blends self and the rest of data, which must all be the same type as self
+    fn blend_dyn(&self, data: &[&dyn DynWorking], weights: &[f32]) -> Box<dyn DynWorking>;
+}
+
+impl<T: Blendable + Any + Send + Sync> DynWorking for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn blend_dyn(&self, data: &[&dyn DynWorking], weights: &[f32]) -> Box<dyn DynWorking> {
+        let data: Vec<&T> = data
+            .iter()
+            .map(|working| working.as_any().downcast_ref().unwrap())
+            .collect();
+
+        Box::new(T::blend(&data, weights))
+    }
+}
+
+pub struct BoxedWorking(pub Box<dyn DynWorking>);
+
+impl Blendable for BoxedWorking {
+    fn blend(data: &[&Self], weights: &[f32]) -> Self {
+        let erased: Vec<&dyn DynWorking> = data.iter().map(|working| &*working.0).collect();
+        BoxedWorking(erased[0].blend_dyn(&erased, weights))
+    }
+}
+
+// implemented for every Shader whose types are 'static, so any shader can be boxed as-is. the
+// uniform passed alongside a boxed shader must hold that shader's Uniform type, or the stages
+// panic
+pub trait DynShader: Sync {
+    fn vertex_stage(&self, context: &VertexContext<DynUniform>) -> VertexOutput<BoxedWorking>;
+    fn fragment_stage_dual(
+        &self,
+        context: &FragmentContext<DynUniform, BoxedWorking>,
+    ) -> (u32, Option<u32>);
+
+    fn clip_position(&self, working: &BoxedWorking) -> Option<Point3<f32>>;
+    fn point_corner(&self, working: BoxedWorking, uv: Point2<f32>) -> BoxedWorking;
+    fn instance_bounds(&self, instance_id: usize, data: &DynUniform) -> Option<InstanceBounds>;
+    fn prepare_instance(&self, data: &DynUniform, instance_id: usize) -> InstanceCache;
+    fn tessellate_vertex(
+        &self,
+        output: VertexOutput<BoxedWorking>,
+        barycentric: Point3<f32>,
+    ) -> VertexOutput<BoxedWorking>;
+}
+
+impl<T> DynShader for T
+where
+    T: Shader + Sync,
+    T::Uniform: 'static,
+    T::Working: Send + 'static,
+{
+    fn vertex_stage(&self, context: &VertexContext<DynUniform>) -> VertexOutput<BoxedWorking> {
+        let output = Shader::vertex_stage(
+            self,
+            &VertexContext {
+                vertex_id: context.vertex_id,
+                instance_id: context.instance_id,
+                instance: context.instance,
+                data: context.data.downcast_ref().unwrap(),
+            },
+        );
+
+        VertexOutput {
+            position: output.position,
+            data: BoxedWorking(Box::new(output.data)),
+        }
+    }
+
+    fn fragment_stage_dual(
+        &self,
+        context: &FragmentContext<DynUniform, BoxedWorking>,
+    ) -> (u32, Option<u32>) {
+        // the context only lends out the working data, so take a copy by blending it with itself
+        let working: &T::Working = context.working.0.as_any().downcast_ref().unwrap();
+
+        #[allow(deprecated)]
+        Shader::fragment_stage_dual(
+            self,
+            &FragmentContext {
+                instance_id: context.instance_id,
+                position: context.position,
+                frag_coord: context.frag_coord,
+                dst_color: context.dst_color,
+                front_facing: context.front_facing,
+                data: context.data.downcast_ref().unwrap(),
+                working: T::Working::blend(&[working], &[1.0]),
+            },
+        )
+    }
+
+    fn clip_position(&self, working: &BoxedWorking) -> Option<Point3<f32>> {
+        Shader::clip_position(self, working.0.as_any().downcast_ref().unwrap())
+    }
+
+    fn point_corner(&self, working: BoxedWorking, uv: Point2<f32>) -> BoxedWorking {
+        let working = *working.0.into_any().downcast::<T::Working>().unwrap();
+        BoxedWorking(Box::new(Shader::point_corner(self, working, uv)))
+    }
+
+    fn instance_bounds(&self, instance_id: usize, data: &DynUniform) -> Option<InstanceBounds> {
+        Shader::instance_bounds(self, instance_id, data.downcast_ref().unwrap())
+    }
+
+    fn prepare_instance(&self, data: &DynUniform, instance_id: usize) -> InstanceCache {
+        Shader::prepare_instance(self, data.downcast_ref().unwrap(), instance_id)
+    }
+
+    fn tessellate_vertex(
+        &self,
+        output: VertexOutput<BoxedWorking>,
+        barycentric: Point3<f32>,
+    ) -> VertexOutput<BoxedWorking> {
+        let output = Shader::tessellate_vertex(
+            self,
+            VertexOutput {
+                position: output.position,
+                data: *output.data.0.into_any().downcast::<T::Working>().unwrap(),
+            },
+            barycentric,
+        );
+
+        VertexOutput {
+            position: output.position,
+            data: BoxedWorking(Box::new(output.data)),
+        }
+    }
+}
+
+impl Shader for Box<dyn DynShader> {
+    type Uniform = DynUniform;
+    type Working = BoxedWorking;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        (**self).vertex_stage(context)
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        (**self).fragment_stage_dual(context).0
+    }
+
+    fn fragment_stage_dual(
+        &self,
+        context: &FragmentContext<Self::Uniform, Self::Working>,
+    ) -> (u32, Option<u32>) {
+        (**self).fragment_stage_dual(context)
+    }
+
+    fn clip_position(&self, working: &Self::Working) -> Option<Point3<f32>> {
+        (**self).clip_position(working)
+    }
+
+    fn point_corner(&self, working: Self::Working, uv: Point2<f32>) -> Self::Working {
+        (**self).point_corner(working, uv)
+    }
+
+    fn instance_bounds(&self, instance_id: usize, data: &Self::Uniform) -> Option<InstanceBounds> {
+        (**self).instance_bounds(instance_id, data)
+    }
+
+    fn prepare_instance(&self, data: &Self::Uniform, instance_id: usize) -> InstanceCache {
+        (**self).prepare_instance(data, instance_id)
+    }
+
+    fn tessellate_vertex(
+        &self,
+        output: VertexOutput<Self::Working>,
+        barycentric: Point3<f32>,
+    ) -> VertexOutput<Self::Working> {
+        (**self).tessellate_vertex(output, barycentric)
+    }
+}
@@ -1,6 +1,14 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::iter::{self, Iterator};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::slice::{self, ChunksExactMut};
+use std::sync::{Arc, Mutex};
 
+use super::color::PixelFormat;
 use super::image::Image;
+use super::scissor::Scissor;
 
 fn fill_image<T: Sized + Copy>(attachment: &mut Image<T>, value: T) {
     for (x, y) in attachment.coordinates() {
@@ -8,12 +16,343 @@ fn fill_image<T: Sized + Copy>(attachment: &mut Image<T>, value: T) {
     }
 }
 
+// region must already be clipped to the image
+fn fill_region<T: Sized + Copy>(attachment: &mut Image<T>, region: &Scissor, value: T) {
+    for y in region.y..region.y + region.height {
+        if let Some(row) = attachment.row_mut(y) {
+            row[region.x..region.x + region.width].fill(value);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FramebufferError {
+    NoAttachments,
+    AttachmentSizeMismatch,
+    ZeroSize,
+    InvalidClearDepth,
+    StencilUnsupported,
+    MultisamplingUnsupported,
+    ClearDepthOutOfRange,
+    InvalidDepthFormat,
+    NoSuchAttachment,
+}
+
+impl Display for FramebufferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::NoAttachments =>
+                    "Framebuffer needs at least one attachment to infer its size!",
+                Self::AttachmentSizeMismatch => "Attachment size does not match the framebuffer!",
+                Self::ZeroSize => "Framebuffer width and height must be nonzero!",
+                Self::InvalidClearDepth => "Depth clear value must be finite!",
+                Self::StencilUnsupported => "Stencil attachments are not supported yet!",
+                Self::NoSuchAttachment => "Color attachment index is out of range!",
+                Self::ClearDepthOutOfRange =>
+                    "Depth clear value is out of range for the depth format!",
+                Self::InvalidDepthFormat =>
+                    "Normalized depth formats need a finite range with near below far!",
+                Self::MultisamplingUnsupported =>
+                    "Sample counts other than 1 are not supported yet!",
+            }
+        )
+    }
+}
+
+impl Error for FramebufferError {}
+
+// copies the overlapping top-left region of src into dst
+fn copy_image<T: Sized + Copy>(src: &Image<T>, dst: &mut Image<T>) {
+    let (src_width, src_height) = src.size();
+    let (dst_width, dst_height) = dst.size();
+
+    let width = src_width.min(dst_width);
+    for y in 0..src_height.min(dst_height) {
+        if let (Some(src_row), Some(dst_row)) = (src.row(y), dst.row_mut(y)) {
+            dst_row[..width].copy_from_slice(&src_row[..width]);
+        }
+    }
+}
+
+// how the depth attachment is stored. F32 keeps depths exactly as they are written. the
+// normalized formats map [near, far] onto 2^16 or 2^24 evenly spaced steps and store the step in
+// a u16 or the low bits of a u32, so surfaces closer together than (far - near) / (2^bits - 1)
+// z-fight where f32 would have kept them apart. depths outside of [near, far] are clamped when
+// written, and fragments are quantized the same way before the depth test. there's no 3 byte
+// integer, so Unorm24 takes as much memory as F32 and only Unorm16 saves any
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum DepthFormat {
+    #[default]
+    F32,
+    Unorm16 {
+        near: f32,
+        far: f32,
+    },
+    Unorm24 {
+        near: f32,
+        far: f32,
+    },
+}
+
+impl DepthFormat {
+    // the mapped range and the largest stored value, None for F32
+    fn range(&self) -> Option<(f32, f32, u32)> {
+        match *self {
+            DepthFormat::F32 => None,
+            DepthFormat::Unorm16 { near, far } => Some((near, far, 0xFFFF)),
+            DepthFormat::Unorm24 { near, far } => Some((near, far, 0xFFFFFF)),
+        }
+    }
+
+    // normalized formats need a finite, non-empty range to map
+    pub fn is_valid(&self) -> bool {
+        match self.range() {
+            Some((near, far, _)) => near.is_finite() && far.is_finite() && near < far,
+            None => true,
+        }
+    }
+
+    // whether the depth can be stored without clamping
+    pub fn contains(&self, depth: f32) -> bool {
+        match self.range() {
+            Some((near, far, _)) => (near..=far).contains(&depth),
+            None => !depth.is_nan(),
+        }
+    }
+
+    // the nearest depth the format can hold. normalized formats clamp to [near, far]
+    pub fn quantize(&self, depth: f32) -> f32 {
+        match self.range() {
+            Some(_) => self.decode(self.encode(depth)),
+            None => depth,
+        }
+    }
+
+    fn encode(&self, depth: f32) -> u32 {
+        let (near, far, max) = self.range().unwrap();
+        let normalized = ((depth - near) / (far - near)).clamp(0.0, 1.0);
+
+        // nan has no meaningful step, so it's pushed to the far plane like an infinite depth
+        match normalized.is_nan() {
+            true => max,
+            false => (normalized * max as f32).round() as u32,
+        }
+    }
+
+    fn decode(&self, value: u32) -> f32 {
+        let (near, far, max) = self.range().unwrap();
+        near + (value as f32 / max as f32) * (far - near)
+    }
+}
+
+enum DepthStorage {
+    F32(Image<f32>),
+    Unorm16(Image<u16>),
+    Unorm24(Image<u32>),
+}
+
+// a depth attachment, stored the way its DepthFormat says. reads decode back to depths and writes
+// quantize, so callers only ever see f32
+pub struct DepthBuffer {
+    format: DepthFormat,
+    storage: DepthStorage,
+}
+
+impl DepthBuffer {
+    pub fn new(width: usize, height: usize, format: DepthFormat) -> DepthBuffer {
+        DepthBuffer {
+            format,
+            storage: match format {
+                DepthFormat::F32 => DepthStorage::F32(Image::new(width, height)),
+                DepthFormat::Unorm16 { .. } => DepthStorage::Unorm16(Image::new(width, height)),
+                DepthFormat::Unorm24 { .. } => DepthStorage::Unorm24(Image::new(width, height)),
+            },
+        }
+    }
+
+    // stores every depth of image in format, clamping any the format can't hold
+    pub fn from_image(image: &Image<f32>, format: DepthFormat) -> DepthBuffer {
+        let (width, height) = image.size();
+        let mut buffer = DepthBuffer::new(width, height, format);
+        for (x, y) in image.coordinates() {
+            buffer.store(x, y, *image.at(x, y).unwrap());
+        }
+
+        buffer
+    }
+
+    pub fn format(&self) -> DepthFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        match &self.storage {
+            DepthStorage::F32(image) => image.size(),
+            DepthStorage::Unorm16(image) => image.size(),
+            DepthStorage::Unorm24(image) => image.size(),
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        match &self.storage {
+            DepthStorage::F32(_) => mem::size_of::<f32>(),
+            DepthStorage::Unorm16(_) => mem::size_of::<u16>(),
+            DepthStorage::Unorm24(_) => mem::size_of::<u32>(),
+        }
+    }
+
+    pub fn at(&self, x: usize, y: usize) -> Option<f32> {
+        match &self.storage {
+            DepthStorage::F32(image) => image.at(x, y).copied(),
+            DepthStorage::Unorm16(image) => image
+                .at(x, y)
+                .map(|value| self.format.decode(*value as u32)),
+            DepthStorage::Unorm24(image) => image.at(x, y).map(|value| self.format.decode(*value)),
+        }
+    }
+
+    // returns false if (x, y) is outside of the buffer
+    pub fn store(&mut self, x: usize, y: usize, depth: f32) -> bool {
+        match &mut self.storage {
+            DepthStorage::F32(image) => image.exchange(x, y, depth).is_some(),
+            DepthStorage::Unorm16(image) => {
+                let value = self.format.encode(depth) as u16;
+                image.exchange(x, y, value).is_some()
+            }
+            DepthStorage::Unorm24(image) => {
+                let value = self.format.encode(depth);
+                image.exchange(x, y, value).is_some()
+            }
+        }
+    }
+
+    // f32 images are taken as they are, without a copy
+    fn from_f32(image: Image<f32>, format: DepthFormat) -> DepthBuffer {
+        match format {
+            DepthFormat::F32 => DepthBuffer {
+                format,
+                storage: DepthStorage::F32(image),
+            },
+            _ => DepthBuffer::from_image(&image, format),
+        }
+    }
+
+    // every depth decoded into an f32 image
+    pub fn to_image(&self) -> Image<f32> {
+        let (width, height) = self.size();
+        let mut image = Image::new(width, height);
+        for (x, y) in image.coordinates() {
+            image.exchange(x, y, self.at(x, y).unwrap());
+        }
+
+        image
+    }
+
+    fn into_image(self) -> Image<f32> {
+        match self.storage {
+            DepthStorage::F32(image) => image,
+            _ => self.to_image(),
+        }
+    }
+
+    fn fill(&mut self, region: &Scissor, depth: f32) {
+        match &mut self.storage {
+            DepthStorage::F32(image) => fill_region(image, region, depth),
+            DepthStorage::Unorm16(image) => {
+                fill_region(image, region, self.format.encode(depth) as u16)
+            }
+            DepthStorage::Unorm24(image) => fill_region(image, region, self.format.encode(depth)),
+        }
+    }
+
+    // src and dst have the same format, so the stored values can be copied as they are
+    fn copy_from(&mut self, src: &DepthBuffer) {
+        match (&src.storage, &mut self.storage) {
+            (DepthStorage::F32(src), DepthStorage::F32(dst)) => copy_image(src, dst),
+            (DepthStorage::Unorm16(src), DepthStorage::Unorm16(dst)) => copy_image(src, dst),
+            (DepthStorage::Unorm24(src), DepthStorage::Unorm24(dst)) => copy_image(src, dst),
+            _ => unreachable!("Depth buffers of different formats!"),
+        }
+    }
+
+    fn rows(&mut self, start: usize, end: usize) -> DepthRows<'_> {
+        let width = self.size().0;
+        match &mut self.storage {
+            DepthStorage::F32(image) => {
+                DepthRows::F32(image.data_mut()[start..end].chunks_exact_mut(width))
+            }
+            DepthStorage::Unorm16(image) => {
+                DepthRows::Unorm16(image.data_mut()[start..end].chunks_exact_mut(width))
+            }
+            DepthStorage::Unorm24(image) => {
+                DepthRows::Unorm24(image.data_mut()[start..end].chunks_exact_mut(width))
+            }
+        }
+    }
+}
+
+enum DepthRows<'a> {
+    F32(ChunksExactMut<'a, f32>),
+    Unorm16(ChunksExactMut<'a, u16>),
+    Unorm24(ChunksExactMut<'a, u32>),
+}
+
+impl<'a> DepthRows<'a> {
+    fn next(&mut self, format: DepthFormat) -> Option<DepthRow<'a>> {
+        let storage = match self {
+            DepthRows::F32(rows) => DepthRowStorage::F32(rows.next()?),
+            DepthRows::Unorm16(rows) => DepthRowStorage::Unorm16(rows.next()?),
+            DepthRows::Unorm24(rows) => DepthRowStorage::Unorm24(rows.next()?),
+        };
+
+        Some(DepthRow { format, storage })
+    }
+}
+
+enum DepthRowStorage<'a> {
+    F32(&'a mut [f32]),
+    Unorm16(&'a mut [u16]),
+    Unorm24(&'a mut [u32]),
+}
+
+// one row of a DepthBuffer, decoded and quantized the same way
+pub struct DepthRow<'a> {
+    format: DepthFormat,
+    storage: DepthRowStorage<'a>,
+}
+
+impl DepthRow<'_> {
+    pub fn get(&self, x: usize) -> f32 {
+        match &self.storage {
+            DepthRowStorage::F32(row) => row[x],
+            DepthRowStorage::Unorm16(row) => self.format.decode(row[x] as u32),
+            DepthRowStorage::Unorm24(row) => self.format.decode(row[x]),
+        }
+    }
+
+    pub fn set(&mut self, x: usize, depth: f32) {
+        match &mut self.storage {
+            DepthRowStorage::F32(row) => row[x] = depth,
+            DepthRowStorage::Unorm16(row) => row[x] = self.format.encode(depth) as u16,
+            DepthRowStorage::Unorm24(row) => row[x] = self.format.encode(depth),
+        }
+    }
+}
+
 pub struct Framebuffer {
     width: usize,
     height: usize,
 
     color: Vec<Image<u32>>,
-    depth: Option<Image<f32>>,
+    color_format: PixelFormat,
+    depth: Option<DepthBuffer>,
+    depth_format: DepthFormat,
+
+    // number of fragments generated per pixel, only tracked when enabled
+    overdraw: Option<Image<u32>>,
 }
 
 #[derive(Debug)]
@@ -22,107 +361,594 @@ pub struct ClearValue {
     pub depth: f32,
 }
 
+impl ClearValue {
+    // a NaN or infinite depth would make every following depth test pass or fail
+    pub fn is_valid(&self) -> bool {
+        self.depth.is_finite()
+    }
+}
+
+// everything needed to create a framebuffer, so call sites don't have to pass bare counts and
+// bools. stencil and samples are there for when those are supported, until then they have to be
+// false and 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramebufferDesc {
+    pub width: usize,
+    pub height: usize,
+
+    pub color_attachments: usize,
+    pub depth: bool,
+    pub depth_format: DepthFormat,
+    pub stencil: bool,
+    pub samples: usize,
+}
+
+impl FramebufferDesc {
+    // a single color attachment and nothing else
+    pub fn new(width: usize, height: usize) -> FramebufferDesc {
+        FramebufferDesc {
+            width,
+            height,
+
+            color_attachments: 1,
+            depth: false,
+            depth_format: DepthFormat::F32,
+            stencil: false,
+            samples: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramebufferInfo {
+    pub width: usize,
+    pub height: usize,
+
+    pub color_attachments: usize,
+    pub color_format: PixelFormat,
+    pub has_depth: bool,
+    pub depth_format: DepthFormat,
+
+    // always 1 until multisampling is supported
+    pub sample_count: usize,
+}
+
+// the color rows of a scanline, one per attachment. most framebuffers have at most one color
+// attachment, which is kept inline so that iterating scanlines doesn't allocate for every row
+pub enum ColorRows<'a> {
+    Single(Option<&'a mut [u32]>),
+    Multiple(Vec<&'a mut [u32]>),
+}
+
+impl<'a> Deref for ColorRows<'a> {
+    type Target = [&'a mut [u32]];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ColorRows::Single(Some(row)) => slice::from_ref(row),
+            ColorRows::Single(None) => &[],
+            ColorRows::Multiple(rows) => rows,
+        }
+    }
+}
+
+impl DerefMut for ColorRows<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            ColorRows::Single(Some(row)) => slice::from_mut(row),
+            ColorRows::Single(None) => &mut [],
+            ColorRows::Multiple(rows) => rows,
+        }
+    }
+}
+
 pub struct MutableScanline<'a> {
     pub y: usize,
-    pub color: Vec<&'a mut [u32]>,
-    pub depth: Option<&'a mut [f32]>,
+    pub color: ColorRows<'a>,
+    pub color_format: PixelFormat,
+    pub depth: Option<DepthRow<'a>>,
+    pub depth_format: DepthFormat,
+    pub overdraw: Option<&'a mut [u32]>,
 }
 
 impl Framebuffer {
     pub fn new(width: usize, height: usize, num_color: usize, has_depth: bool) -> Framebuffer {
-        Framebuffer {
-            width: width,
-            height: height,
+        let desc = FramebufferDesc {
+            color_attachments: num_color,
+            depth: has_depth,
+            ..FramebufferDesc::new(width, height)
+        };
+
+        // the size is the only thing that can be wrong here
+        Self::from_desc(&desc).unwrap_or_else(|_| {
+            panic!(
+                "Framebuffer width and height must be nonzero, got {}x{}!",
+                width, height
+            )
+        })
+    }
 
-            color: Vec::from_iter(iter::repeat_with(|| Image::new(width, height)).take(num_color)),
-            depth: match has_depth {
-                true => Some(Image::new(width, height)),
+    pub fn from_desc(desc: &FramebufferDesc) -> Result<Framebuffer, FramebufferError> {
+        let (width, height) = (desc.width, desc.height);
+
+        // everything downstream maps pixels to NDC by dividing by the size
+        if width == 0 || height == 0 {
+            return Err(FramebufferError::ZeroSize);
+        }
+
+        if desc.stencil {
+            return Err(FramebufferError::StencilUnsupported);
+        }
+
+        if desc.samples != 1 {
+            return Err(FramebufferError::MultisamplingUnsupported);
+        }
+
+        if !desc.depth_format.is_valid() {
+            return Err(FramebufferError::InvalidDepthFormat);
+        }
+
+        let color = iter::repeat_with(|| Image::new(width, height));
+        Ok(Framebuffer {
+            width,
+            height,
+
+            color: Vec::from_iter(color.take(desc.color_attachments)),
+            color_format: PixelFormat::default(),
+            depth: match desc.depth {
+                true => Some(DepthBuffer::new(width, height, desc.depth_format)),
                 false => None,
             },
+            depth_format: desc.depth_format,
+
+            overdraw: None,
+        })
+    }
+
+    pub fn with_attachments(
+        color: Vec<Image<u32>>,
+        depth: Option<Image<f32>>,
+    ) -> Result<Framebuffer, FramebufferError> {
+        let (width, height) = match (color.first(), &depth) {
+            (Some(attachment), _) => attachment.size(),
+            (None, Some(attachment)) => attachment.size(),
+            (None, None) => return Err(FramebufferError::NoAttachments),
+        };
+
+        let sizes_match = color
+            .iter()
+            .map(|attachment| attachment.size())
+            .chain(depth.iter().map(|attachment| attachment.size()))
+            .all(|size| size == (width, height));
+
+        if !sizes_match {
+            return Err(FramebufferError::AttachmentSizeMismatch);
+        }
+
+        if width == 0 || height == 0 {
+            return Err(FramebufferError::ZeroSize);
         }
+
+        Ok(Framebuffer {
+            width,
+            height,
+
+            color,
+            color_format: PixelFormat::default(),
+            depth: depth.map(|depth| DepthBuffer::from_f32(depth, DepthFormat::F32)),
+            depth_format: DepthFormat::F32,
+
+            overdraw: None,
+        })
     }
 
     pub fn size(&self) -> (usize, usize) {
         (self.width, self.height)
     }
 
+    pub fn info(&self) -> FramebufferInfo {
+        FramebufferInfo {
+            width: self.width,
+            height: self.height,
+
+            color_attachments: self.color.len(),
+            color_format: self.color_format,
+            has_depth: self.depth.is_some(),
+            depth_format: self.depth_format,
+
+            sample_count: 1,
+        }
+    }
+
+    // bytes held by every attachment, overdraw counts included. depth takes however many bytes
+    // its format stores per pixel. there's no stencil and every pixel has a single sample, so
+    // neither adds anything yet
+    pub fn memory_bytes(&self) -> usize {
+        let pixels = self.width * self.height;
+        let color = self.color.len() * pixels * mem::size_of::<u32>();
+        let depth = match &self.depth {
+            Some(depth) => pixels * depth.bytes_per_pixel(),
+            None => 0,
+        };
+
+        let overdraw = match &self.overdraw {
+            Some(_) => pixels * mem::size_of::<u32>(),
+            None => 0,
+        };
+
+        (color + depth + overdraw) * self.info().sample_count
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize, preserve_contents: bool) {
+        let mut resized = Framebuffer::new(width, height, self.color.len(), false);
+        resized.color_format = self.color_format;
+        resized.depth_format = self.depth_format;
+        resized.depth = self
+            .depth
+            .as_ref()
+            .map(|_| DepthBuffer::new(width, height, self.depth_format));
+
+        if preserve_contents {
+            for (src, dst) in self.color.iter().zip(resized.color.iter_mut()) {
+                copy_image(src, dst);
+            }
+
+            if let (Some(src), Some(dst)) = (&self.depth, &mut resized.depth) {
+                dst.copy_from(src);
+            }
+        }
+
+        if let Some(src) = &self.overdraw {
+            let mut dst = Image::new(width, height);
+            if preserve_contents {
+                copy_image(src, &mut dst);
+            }
+
+            resized.overdraw = Some(dst);
+        }
+
+        *self = resized;
+    }
+
     pub fn color_attachments(&self) -> &Vec<Image<u32>> {
         &self.color
     }
 
-    pub fn depth_attachment(&self) -> &Option<Image<f32>> {
+    pub fn color_attachment(&self, index: usize) -> Option<&Image<u32>> {
+        self.color.get(index)
+    }
+
+    // the image can be modified in place but not replaced, so it always keeps the framebuffer's
+    // size
+    pub fn color_attachment_mut(&mut self, index: usize) -> Option<&mut Image<u32>> {
+        self.color.get_mut(index)
+    }
+
+    pub fn color_format(&self) -> PixelFormat {
+        self.color_format
+    }
+
+    // only changes how existing contents are interpreted, nothing is converted
+    pub fn set_color_format(&mut self, format: PixelFormat) {
+        self.color_format = format;
+    }
+
+    pub fn depth_format(&self) -> DepthFormat {
+        self.depth_format
+    }
+
+    // unlike set_color_format the storage changes with the format, so existing depths are
+    // converted, clamping any that fall outside of the new range
+    pub fn set_depth_format(&mut self, format: DepthFormat) -> Result<(), FramebufferError> {
+        if !format.is_valid() {
+            return Err(FramebufferError::InvalidDepthFormat);
+        }
+
+        self.depth_format = format;
+        if let Some(depth) = &mut self.depth {
+            *depth = DepthBuffer::from_image(&depth.to_image(), format);
+        }
+
+        Ok(())
+    }
+
+    pub fn depth_attachment(&self) -> &Option<DepthBuffer> {
         &self.depth
     }
 
-    pub fn clear(&mut self, value: &ClearValue) {
+    // the depths are decoded from the depth format
+    pub fn take_depth(&mut self) -> Option<Image<f32>> {
+        self.depth.take().map(DepthBuffer::into_image)
+    }
+
+    // the depths are stored in the framebuffer's depth format, clamping any it can't hold
+    pub fn set_depth(&mut self, depth: Option<Image<f32>>) -> Result<(), FramebufferError> {
+        if let Some(attachment) = &depth
+            && attachment.size() != self.size()
+        {
+            return Err(FramebufferError::AttachmentSizeMismatch);
+        }
+
+        self.depth = depth.map(|depth| DepthBuffer::from_f32(depth, self.depth_format));
+        Ok(())
+    }
+
+    pub fn overdraw(&self) -> &Option<Image<u32>> {
+        &self.overdraw
+    }
+
+    // overdraw tracking costs a write per fragment, so it is off by default
+    pub fn set_overdraw_tracking(&mut self, enabled: bool) {
+        self.overdraw = match enabled {
+            true => Some(Image::new(self.width, self.height)),
+            false => None,
+        };
+    }
+
+    // maps overdraw counts onto a blue to red ramp, normalized to the highest count. untouched
+    // pixels are black
+    pub fn overdraw_heatmap(&self) -> Image<u32> {
+        let mut heatmap = Image::new(self.width, self.height);
+        let Some(overdraw) = &self.overdraw else {
+            return heatmap;
+        };
+
+        let max_count = overdraw.data().iter().copied().max().unwrap_or(0);
+        for (x, y) in overdraw.coordinates() {
+            let count = *overdraw.at(x, y).unwrap();
+            let color = match count {
+                0 => 0x000000FF,
+                _ => {
+                    let t = match max_count {
+                        1 => 1.0,
+                        _ => (count - 1) as f32 / (max_count - 1) as f32,
+                    };
+
+                    let red = (t * 255.0).round() as u32;
+                    let blue = 255 - red;
+
+                    (red << 24) | (blue << 8) | 0xFF
+                }
+            };
+
+            heatmap.exchange(x, y, color);
+        }
+
+        heatmap
+    }
+
+    // normalized depth formats can't hold depths outside of [near, far], so clearing to one is an
+    // error rather than silently clamping
+    fn validate_clear(&self, value: &ClearValue) -> Result<(), FramebufferError> {
+        if !value.is_valid() {
+            Err(FramebufferError::InvalidClearDepth)
+        } else if self.depth.is_some() && !self.depth_format.contains(value.depth) {
+            Err(FramebufferError::ClearDepthOutOfRange)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn clear(&mut self, value: &ClearValue) -> Result<(), FramebufferError> {
+        self.validate_clear(value)?;
+
         for attachment in &mut self.color {
             fill_image(attachment, value.color);
         }
 
         if let Some(depth) = &mut self.depth {
-            fill_image(depth, value.depth);
+            let full = Scissor {
+                x: 0,
+                y: 0,
+                width: self.width,
+                height: self.height,
+            };
+
+            depth.fill(&full, value.depth);
+        }
+
+        if let Some(overdraw) = &mut self.overdraw {
+            fill_image(overdraw, 0);
+        }
+
+        Ok(())
+    }
+
+    // same as clear, but only touches pixels inside the region
+    pub fn clear_region(
+        &mut self,
+        value: &ClearValue,
+        region: &Scissor,
+    ) -> Result<(), FramebufferError> {
+        self.validate_clear(value)?;
+
+        let full = Scissor {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        };
+
+        let Some(region) = region.intersect_with(&full) else {
+            return Ok(());
+        };
+
+        for attachment in &mut self.color {
+            fill_region(attachment, &region, value.color);
+        }
+
+        if let Some(depth) = &mut self.depth {
+            depth.fill(&region, value.depth);
         }
+
+        if let Some(overdraw) = &mut self.overdraw {
+            fill_region(overdraw, &region, 0);
+        }
+
+        Ok(())
+    }
+
+    // calls f with the coordinates and color of every pixel of one attachment inside region, which
+    // is clipped to the framebuffer. rows are visited top to bottom
+    pub fn for_each_pixel_in<F>(
+        &mut self,
+        attachment: usize,
+        region: &Scissor,
+        mut f: F,
+    ) -> Result<(), FramebufferError>
+    where
+        F: FnMut(usize, usize, &mut u32),
+    {
+        let full = Scissor {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        };
+
+        let image = self
+            .color
+            .get_mut(attachment)
+            .ok_or(FramebufferError::NoSuchAttachment)?;
+
+        let Some(region) = region.intersect_with(&full) else {
+            return Ok(());
+        };
+
+        for y in region.y..region.y + region.height {
+            let row = &mut image.row_mut(y).unwrap()[region.x..region.x + region.width];
+            for (i, color) in row.iter_mut().enumerate() {
+                f(region.x + i, y, color);
+            }
+        }
+
+        Ok(())
     }
 
     pub fn scanlines<'a>(&'a mut self, offset: usize, count: usize) -> Vec<MutableScanline<'a>> {
-        if offset >= self.height || offset + count > self.height {
+        self.scanlines_iter(offset, count).collect()
+    }
+
+    // same as scanlines, but yields one scanline at a time instead of collecting them up front
+    pub fn scanlines_iter<'a>(&'a mut self, offset: usize, count: usize) -> ScanlineIterator<'a> {
+        // an empty range is fine anywhere up to the bottom edge
+        if offset + count > self.height {
             panic!("Invalid scanline range!");
         }
 
         let start = offset * self.width;
         let end = (offset + count) * self.width;
 
-        // vector of advancing cursors through the color attachments through the range selected by
-        // the user
-        let mut color_cursors: Vec<_> = self
+        // row iterators through the color attachments over the range selected by the user
+        let color_rows = self
             .color
             .iter_mut()
-            .map(|attachment| &mut attachment.data_mut()[start..end])
+            .map(|attachment| attachment.data_mut()[start..end].chunks_exact_mut(self.width))
             .collect();
 
-        // advancing cursor through depth attachment if one exists
-        let mut depth_cursor = self
+        // row iterator through the depth attachment if one exists
+        let depth_rows = self
             .depth
             .as_mut()
-            .map(|attachment| &mut attachment.data_mut()[start..end]);
+            .map(|attachment| attachment.rows(start, end));
 
-        let mut scanlines = Vec::new();
-        for delta_y in 0..count {
-            let mut color = Vec::new();
-            let mut advanced_cursors = Vec::new();
+        let overdraw_rows = self
+            .overdraw
+            .as_mut()
+            .map(|counter| counter.data_mut()[start..end].chunks_exact_mut(self.width));
 
-            // advance color attachment cursors by a row and append a shorter slice at the previous
-            // location
-            for cursor in color_cursors {
-                let (first, second) = cursor.split_at_mut(self.width);
+        ScanlineIterator {
+            y: offset,
+            end: offset + count,
 
-                color.push(first);
-                advanced_cursors.push(second);
-            }
+            color_rows,
+            color_format: self.color_format,
+            depth_rows,
+            depth_format: self.depth_format,
+            overdraw_rows,
+        }
+    }
+}
 
-            color_cursors = advanced_cursors;
-            scanlines.push(MutableScanline {
-                y: offset + delta_y,
-                color,
+pub struct ScanlineIterator<'a> {
+    y: usize,
+    end: usize,
 
-                // i would rather use Option::map but it moves the value regardless of if its
-                // Some(_) or None so we have to match
-                depth: match depth_cursor {
-                    // if we have a depth attachment, advance it by a row and return a shorter
-                    // slice at the previous location
-                    Some(cursor) => {
-                        let (first, second) = cursor.split_at_mut(self.width);
-                        depth_cursor = Some(second);
+    color_rows: Vec<ChunksExactMut<'a, u32>>,
+    color_format: PixelFormat,
+    depth_rows: Option<DepthRows<'a>>,
+    depth_format: DepthFormat,
+    overdraw_rows: Option<ChunksExactMut<'a, u32>>,
+}
 
-                        Some(first)
-                    }
+impl<'a> Iterator for ScanlineIterator<'a> {
+    type Item = MutableScanline<'a>;
 
-                    // otherwise we dont care
-                    None => None,
-                },
-            });
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.y >= self.end {
+            return None;
         }
 
-        scanlines
+        // each row iterator yields exactly one row per scanline, so unwrapping is fine
+        let attachments = self.color_rows.len();
+        let mut color_rows = self.color_rows.iter_mut().map(|rows| rows.next().unwrap());
+        let color = match attachments {
+            0 | 1 => ColorRows::Single(color_rows.next()),
+            _ => ColorRows::Multiple(color_rows.collect()),
+        };
+
+        let scanline = MutableScanline {
+            y: self.y,
+            color,
+            color_format: self.color_format,
+            depth: self
+                .depth_rows
+                .as_mut()
+                .map(|rows| rows.next(self.depth_format).unwrap()),
+            depth_format: self.depth_format,
+            overdraw: self.overdraw_rows.as_mut().map(|rows| rows.next().unwrap()),
+        };
+
+        self.y += 1;
+        Some(scanline)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.y;
+        (remaining, Some(remaining))
+    }
+}
+
+// a pair of identical framebuffers. render into the back buffer while presenting the front, then
+// swap once the frame is finished
+pub struct DoubleBuffer {
+    front: Arc<Mutex<Framebuffer>>,
+    back: Arc<Mutex<Framebuffer>>,
+}
+
+impl DoubleBuffer {
+    pub fn new(width: usize, height: usize, num_color: usize, has_depth: bool) -> DoubleBuffer {
+        let create = || {
+            Arc::new(Mutex::new(Framebuffer::new(
+                width, height, num_color, has_depth,
+            )))
+        };
+
+        DoubleBuffer {
+            front: create(),
+            back: create(),
+        }
+    }
+
+    pub fn front(&self) -> &Arc<Mutex<Framebuffer>> {
+        &self.front
+    }
+
+    pub fn back(&self) -> &Arc<Mutex<Framebuffer>> {
+        &self.back
+    }
+
+    pub fn swap(&mut self) {
+        mem::swap(&mut self.front, &mut self.back);
     }
 }
@@ -0,0 +1,65 @@
+use nalgebra::{Matrix4, Point2, Point3, Vector2, Vector3};
+
+use super::rasterizer::WindingOrder;
+
+fn rotate_cw(v: &Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-v.y, v.x)
+}
+
+fn rotate_ccw(v: &Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(v.y, -v.x)
+}
+
+pub(crate) fn signed_triangle_area(points: [&Point2<f32>; 3], winding: WindingOrder) -> f32 {
+    let a = points[0];
+    let b = points[1];
+    let c = points[2];
+
+    let ab = b - a;
+    let ac = c - a;
+
+    let normal = match winding {
+        // rotate counterclockwise 90 deg
+        WindingOrder::CounterClockwise => rotate_ccw(&ab),
+
+        // rotate clockwise 90 deg
+        WindingOrder::Clockwise => rotate_cw(&ab),
+    };
+
+    ac.dot(&normal) / 2.0
+}
+
+// a triangle is front facing if its vertices are in the given winding order on screen. degenerate
+// triangles have no facing and always return false
+pub fn is_front_facing(triangle: [Point2<f32>; 3], winding: WindingOrder) -> bool {
+    signed_triangle_area(triangle.each_ref(), winding) > 0.0
+}
+
+// true if the transform mirrors geometry, reversing the winding order of its triangles. pass the
+// result as IndexedRenderCall::flip_winding
+pub fn flips_winding(model: &Matrix4<f32>) -> bool {
+    model.fixed_view::<3, 3>(0, 0).determinant() < 0.0
+}
+
+// per vertex normals for a triangle list, averaging the normals of every face using the vertex
+// weighted by the face's area. vertices without a face with any area get a zero normal. normals
+// point out of the side the faces are counterclockwise from
+pub fn compute_smooth_normals(positions: &[Point3<f32>], indices: &[u16]) -> Vec<Vector3<f32>> {
+    let mut normals = vec![Vector3::zeros(); positions.len()];
+
+    for face in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| face[i] as usize);
+
+        // the cross product's length is twice the area, which is exactly the weight needed. faces
+        // without any area add nothing
+        let normal = (positions[b] - positions[a]).cross(&(positions[c] - positions[a]));
+        for index in [a, b, c] {
+            normals[index] += normal;
+        }
+    }
+
+    normals
+        .into_iter()
+        .map(|normal| normal.try_normalize(0.0).unwrap_or(Vector3::zeros()))
+        .collect()
+}
@@ -1,6 +1,9 @@
 use std::iter::{self, Iterator};
 use std::mem;
 
+use super::color::{srgb_to_linear, unpack_channels};
+use super::scissor::Scissor;
+
 pub struct Image<T: Sized> {
     data: Vec<T>,
     width: usize,
@@ -41,6 +44,16 @@ impl<T: Sized> Image<T> {
         })
     }
 
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        self.index_of(0, y)
+            .map(|start| &self.data[start..start + self.width])
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        self.index_of(0, y)
+            .map(|start| &mut self.data[start..start + self.width])
+    }
+
     pub fn data<'a>(&'a self) -> &'a [T] {
         &self.data
     }
@@ -64,6 +77,112 @@ impl<T: Sized> Image<T> {
     }
 }
 
+impl<T: Sized + Copy> Image<T> {
+    // copies the region into a new image, or returns None if it doesn't fit within this one
+    pub fn crop(&self, region: &Scissor) -> Option<Image<T>> {
+        if region.x + region.width > self.width || region.y + region.height > self.height {
+            return None;
+        }
+
+        let mut data = Vec::with_capacity(region.width * region.height);
+        for y in region.y..region.y + region.height {
+            let start = y * self.width + region.x;
+            data.extend_from_slice(&self.data[start..start + region.width]);
+        }
+
+        Some(Image {
+            data,
+            width: region.width,
+            height: region.height,
+        })
+    }
+}
+
+impl Image<u32> {
+    // bounding box of every pixel that differs from prev, or None if nothing changed. if the
+    // sizes differ, the whole image counts as changed
+    pub fn changed_region(&self, prev: &Image<u32>) -> Option<Scissor> {
+        if self.size() != prev.size() {
+            return Some(Scissor {
+                x: 0,
+                y: 0,
+                width: self.width,
+                height: self.height,
+            });
+        }
+
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for y in 0..self.height {
+            let start = y * self.width;
+            let row = &self.data[start..start + self.width];
+            let prev_row = &prev.data[start..start + self.width];
+
+            let Some(x0) = row.iter().zip(prev_row).position(|(a, b)| a != b) else {
+                continue;
+            };
+
+            // a difference was found, so searching from the end can't come up empty
+            let x1 = row.iter().zip(prev_row).rposition(|(a, b)| a != b).unwrap();
+
+            bounds = Some(match bounds {
+                Some((min_x, min_y, max_x, _)) => (min_x.min(x0), min_y, max_x.max(x1), y),
+                None => (x0, y, x1, y),
+            });
+        }
+
+        bounds.map(|(min_x, min_y, max_x, max_y)| Scissor {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        })
+    }
+
+    // pixel counts of relative luminance split into `bins` equal buckets over [0, 1], from rec.709
+    // weights on the linearized channels. alpha is ignored. no bins gives an empty histogram
+    pub fn luminance_histogram(&self, bins: usize) -> Vec<u32> {
+        let mut histogram = vec![0; bins];
+        if bins == 0 {
+            return histogram;
+        }
+
+        for pixel in &self.data {
+            let [r, g, b, _] = unpack_channels(*pixel).map(srgb_to_linear);
+            let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+
+            // full white would land one past the last bin
+            let bin = ((luminance * bins as f32) as usize).min(bins - 1);
+            histogram[bin] += 1;
+        }
+
+        histogram
+    }
+}
+
+// pixels are stored as 0xRRGGBBAA, so the big-endian bytes line up with the crate's RGBA8 layout
+#[cfg(feature = "image-crate")]
+impl Image<u32> {
+    pub fn to_rgba_image(&self) -> ::image::RgbaImage {
+        ::image::RgbaImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = self.data[y as usize * self.width + x as usize];
+            ::image::Rgba(color.to_be_bytes())
+        })
+    }
+
+    pub fn from_rgba_image(source: &::image::RgbaImage) -> Image<u32> {
+        let (width, height) = source.dimensions();
+
+        Image {
+            data: source
+                .pixels()
+                .map(|pixel| u32::from_be_bytes(pixel.0))
+                .collect(),
+            width: width as usize,
+            height: height as usize,
+        }
+    }
+}
+
 pub struct CoordinateIterator {
     pixel_index: usize,
     x: usize,
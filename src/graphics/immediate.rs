@@ -0,0 +1,71 @@
+use nalgebra::Point3;
+
+use super::framebuffer::Framebuffer;
+use super::rasterizer::{DepthMode, IndexedRenderCall, Pipeline, Rasterizer, RasterizerError};
+use super::shader::{FragmentContext, Shader, VertexContext, VertexOutput};
+
+// draws a single flat colored triangle without having to write a shader first. meant for quick
+// tests and examples, not for anything where performance matters
+
+struct SolidShader {}
+
+struct SolidUniforms {
+    vertices: [Point3<f32>; 3],
+    color: u32,
+}
+
+impl Shader for SolidShader {
+    type Uniform = SolidUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.color
+    }
+}
+
+impl Rasterizer {
+    // vertices are in pixels, with the origin at the top left unless set otherwise, and z as
+    // depth. z is also the w of the perspective divide, so it has to be positive and finite. both
+    // windings are drawn, and depth is tested and written if fb has a depth attachment
+    pub fn draw_triangle(
+        &mut self,
+        fb: &mut Framebuffer,
+        vertices: [Point3<f32>; 3],
+        color: u32,
+    ) -> Result<(), RasterizerError> {
+        if let Some(vertex) = vertices
+            .iter()
+            .find(|vertex| !(vertex.z > 0.0 && vertex.z.is_finite()))
+        {
+            return Err(RasterizerError::InvalidVertexDepth(vertex.z));
+        }
+
+        let (width, height) = fb.size();
+        let uniforms = SolidUniforms {
+            vertices: vertices.map(|vertex| {
+                Point3::new(
+                    vertex.x / width as f32 * 2.0 - 1.0,
+                    vertex.y / height as f32 * 2.0 - 1.0,
+                    vertex.z,
+                )
+            }),
+            color,
+        };
+
+        // the default state plus depth can't fail to build
+        let pipeline = Pipeline::builder(SolidShader {})
+            .depth(DepthMode::Write)
+            .build()
+            .unwrap();
+
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+        self.render_indexed_into(&call, fb)
+    }
+}
@@ -1,15 +1,36 @@
 mod image;
 mod framebuffer;
+mod cube_map;
+mod color;
 
 mod scissor;
+mod viewport;
 mod shader;
+mod dyn_shader;
+mod source;
+mod topology;
+mod geometry;
+mod camera;
 mod blending;
+mod sampler;
 mod rasterizer;
+mod pipeline;
+mod immediate;
 
 pub use image::*;
 pub use framebuffer::*;
+pub use cube_map::*;
+pub use color::*;
 
 pub use scissor::*;
+pub use viewport::*;
 pub use shader::*;
+pub use dyn_shader::*;
+pub use source::*;
+pub use topology::*;
+pub use geometry::*;
+pub use camera::*;
 pub use blending::*;
+pub use sampler::*;
 pub use rasterizer::*;
+pub use pipeline::*;
@@ -0,0 +1,200 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use super::rasterizer::{
+    AlphaZeroDiscard, BlendAttachment, DepthCompare, DepthMode, LineRasterization, Pipeline,
+    WindingOrder,
+};
+use super::shader::Shader;
+use super::topology::Topology;
+
+#[derive(Debug)]
+pub enum PipelineError {
+    BlendAttachmentCountMismatch { expected: usize, actual: usize },
+    InvalidMinTriangleArea(f32),
+    TriangleStateOnLines,
+}
+
+impl Display for PipelineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlendAttachmentCountMismatch { expected, actual } => write!(
+                f,
+                "Pipeline blends {} attachments but targets {} color attachments!",
+                actual, expected
+            ),
+            Self::InvalidMinTriangleArea(area) => {
+                write!(
+                    f,
+                    "Minimum triangle area must be finite and non-negative, got {}!",
+                    area
+                )
+            }
+            Self::TriangleStateOnLines => {
+                write!(f, "Minimum triangle area has no effect on line topologies!")
+            }
+        }
+    }
+}
+
+impl Error for PipelineError {}
+
+// validates pipeline state on build() so invalid combinations surface before rendering. struct
+// literals skip validation entirely
+pub struct PipelineBuilder<T: Shader> {
+    pipeline: Pipeline<T>,
+    color_attachments: Option<usize>,
+}
+
+impl<T: Shader> Pipeline<T> {
+    pub fn builder(shader: T) -> PipelineBuilder<T> {
+        PipelineBuilder {
+            pipeline: Pipeline {
+                topology: Topology::TriangleList,
+                depth: DepthMode::DontCare,
+                depth_compare: DepthCompare::LessOrEqual,
+                cull_back: false,
+                winding_order: WindingOrder::Clockwise,
+                blending: None,
+                min_triangle_area: None,
+                clamp_barycentric: false,
+                color_key: None,
+                point_size: 1.0,
+                read_dst: false,
+                alpha_zero_discard: AlphaZeroDiscard::Off,
+                line_rasterization: LineRasterization::Aliased,
+                tessellation_level: 1,
+                shader,
+            },
+            color_attachments: None,
+        }
+    }
+}
+
+impl<T: Shader + Clone> Pipeline<T> {
+    // copy of this pipeline for a depth prepass, writing depth with the same geometry state but
+    // without any color state. there is no color write mask, so render it to a framebuffer
+    // without color attachments
+    pub fn with_depth_only(&self) -> Pipeline<T> {
+        Pipeline {
+            depth: DepthMode::Write,
+            blending: None,
+            color_key: None,
+            read_dst: false,
+            ..self.clone()
+        }
+    }
+}
+
+impl<T: Shader> PipelineBuilder<T> {
+    pub fn topology(mut self, topology: Topology) -> Self {
+        self.pipeline.topology = topology;
+        self
+    }
+
+    pub fn depth(mut self, depth: DepthMode) -> Self {
+        self.pipeline.depth = depth;
+        self
+    }
+
+    pub fn depth_compare(mut self, compare: DepthCompare) -> Self {
+        self.pipeline.depth_compare = compare;
+        self
+    }
+
+    pub fn cull_back(mut self, cull_back: bool) -> Self {
+        self.pipeline.cull_back = cull_back;
+        self
+    }
+
+    pub fn winding_order(mut self, winding_order: WindingOrder) -> Self {
+        self.pipeline.winding_order = winding_order;
+        self
+    }
+
+    // None entries write the shaded color to their attachment without blending
+    pub fn blending<B: Into<Option<BlendAttachment>>>(mut self, blending: Vec<B>) -> Self {
+        self.pipeline.blending = Some(
+            blending
+                .into_iter()
+                .map(|attachment| attachment.into().unwrap_or(BlendAttachment::disabled()))
+                .collect(),
+        );
+
+        self
+    }
+
+    pub fn min_triangle_area(mut self, area: f32) -> Self {
+        self.pipeline.min_triangle_area = Some(area);
+        self
+    }
+
+    pub fn clamp_barycentric(mut self, clamp: bool) -> Self {
+        self.pipeline.clamp_barycentric = clamp;
+        self
+    }
+
+    pub fn color_key(mut self, key: u32) -> Self {
+        self.pipeline.color_key = Some(key);
+        self
+    }
+
+    pub fn point_size(mut self, size: f32) -> Self {
+        self.pipeline.point_size = size;
+        self
+    }
+
+    pub fn read_dst(mut self, read_dst: bool) -> Self {
+        self.pipeline.read_dst = read_dst;
+        self
+    }
+
+    pub fn alpha_zero_discard(mut self, discard: AlphaZeroDiscard) -> Self {
+        self.pipeline.alpha_zero_discard = discard;
+        self
+    }
+
+    pub fn line_rasterization(mut self, rasterization: LineRasterization) -> Self {
+        self.pipeline.line_rasterization = rasterization;
+        self
+    }
+
+    pub fn tessellation_level(mut self, level: u32) -> Self {
+        self.pipeline.tessellation_level = level;
+        self
+    }
+
+    // number of color attachments the pipeline will render to, used to validate blending
+    pub fn color_attachments(mut self, count: usize) -> Self {
+        self.color_attachments = Some(count);
+        self
+    }
+
+    pub fn build(self) -> Result<Pipeline<T>, PipelineError> {
+        let pipeline = &self.pipeline;
+
+        if let (Some(expected), Some(blending)) = (self.color_attachments, &pipeline.blending)
+            && blending.len() != expected
+        {
+            return Err(PipelineError::BlendAttachmentCountMismatch {
+                expected,
+                actual: blending.len(),
+            });
+        }
+
+        if let Some(area) = pipeline.min_triangle_area {
+            if !area.is_finite() || area < 0.0 {
+                return Err(PipelineError::InvalidMinTriangleArea(area));
+            }
+
+            if matches!(
+                pipeline.topology,
+                Topology::LineList | Topology::LineStrip | Topology::LineLoop
+            ) {
+                return Err(PipelineError::TriangleStateOnLines);
+            }
+        }
+
+        Ok(self.pipeline)
+    }
+}
@@ -2,32 +2,93 @@ use std::array;
 use std::collections::LinkedList;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 
-use nalgebra::{Point2, Point3, Vector2};
+use nalgebra::{Point2, Point3, Point4, Vector2, Vector4};
+use rayon::ThreadPool;
 use rayon::prelude::*;
 
 use super::blending::Blendable;
-use super::framebuffer::{Framebuffer, MutableScanline};
+use super::color::PixelFormat;
+use super::framebuffer::{ClearValue, Framebuffer, FramebufferDesc, MutableScanline};
+use super::geometry::signed_triangle_area;
+use super::image::Image;
 use super::scissor::Scissor;
-use super::shader::{FragmentContext, Shader, VertexContext, VertexOutput};
+use super::shader::{FragmentContext, InstanceCache, Shader, VertexContext, VertexOutput};
+use super::topology::{Primitive, Topology};
+use super::viewport::Viewport;
 
 #[derive(Debug)]
 pub enum RasterizerError {
     NoRenderTarget,
     RenderTargetUnfinished,
+    RenderTargetStackOverflow,
+    InvalidPrimitiveSize,
+    NoScissor,
+    InvalidClearDepth,
+    ClearDepthOutOfRange,
+    BlendAttachmentCountMismatch { expected: usize, actual: usize },
+
+    // number of indices left over by a list topology
+    IncompletePrimitive(usize),
+    DepthSourceSizeMismatch,
+
+    // max is the vertex count of the call, which no index may reach
+    IndexOutOfRange { index: usize, max: usize },
+
+    // vertex z doubles as w, so it has to be positive to interpolate anything
+    InvalidVertexDepth(f32),
+
+    // the thread of an async render panicked, usually in the shader
+    RenderThreadPanicked,
 }
 
 impl Display for RasterizerError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::NoRenderTarget => "No render target pushed to the stack!",
-                Self::RenderTargetUnfinished => "Render target still present on the stack!",
+        match self {
+            Self::NoRenderTarget => write!(f, "No render target pushed to the stack!"),
+            Self::RenderTargetUnfinished => {
+                write!(f, "Render target still present on the stack!")
             }
-        )
+            Self::RenderTargetStackOverflow => {
+                write!(f, "Too many render targets pushed to the stack!")
+            }
+            Self::InvalidPrimitiveSize => {
+                write!(f, "Point size is negative or NaN!")
+            }
+            Self::NoScissor => write!(f, "No scissor pushed to the stack!"),
+            Self::InvalidClearDepth => write!(f, "Depth clear value must be finite!"),
+            Self::ClearDepthOutOfRange => {
+                write!(f, "Depth clear value is out of range for the depth format!")
+            }
+            Self::BlendAttachmentCountMismatch { expected, actual } => write!(
+                f,
+                "Pipeline blends {} attachments but the render target has {}!",
+                actual, expected
+            ),
+            Self::IncompletePrimitive(count) => write!(
+                f,
+                "{} trailing indices don't form a complete primitive!",
+                count
+            ),
+            Self::DepthSourceSizeMismatch => {
+                write!(f, "Depth source does not match the render target size!")
+            }
+            Self::IndexOutOfRange { index, max } => {
+                write!(f, "Index {} is out of range for {} vertices!", index, max)
+            }
+            Self::InvalidVertexDepth(depth) => {
+                write!(
+                    f,
+                    "Vertex depth must be positive and finite, got {}!",
+                    depth
+                )
+            }
+            Self::RenderThreadPanicked => write!(f, "Async render thread panicked!"),
+        }
     }
 }
 
@@ -41,6 +102,20 @@ pub enum BlendFactor {
     OneMinusSrcAlpha,
     DstAlpha,
     OneMinusDstAlpha,
+
+    // per channel, so the red term is scaled by the red channel and so on. DstColor with Zero
+    // multiplies, One with OneMinusSrcColor screens
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+
+    // dual source factors, reading the secondary color from Shader::fragment_stage_dual. they
+    // read as zero if the shader doesn't output one
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -48,78 +123,114 @@ pub enum BlendOp {
     Add,
     SrcSubDst,
     DstSubSrc,
+
+    // per channel minimum and maximum of source and destination. the factors are ignored, like
+    // in OpenGL
+    Min,
+    Max,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ComponentBlendOp {
     pub op: BlendOp,
     pub src_factor: BlendFactor,
     pub dst_factor: BlendFactor,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BlendAttachment {
     pub color: Option<ComponentBlendOp>,
     pub alpha: Option<ComponentBlendOp>,
 }
 
-fn color_to_channels(color: u32) -> [f32; 4] {
-    color.to_be_bytes().map(|c| (c as f32) / 256.0)
-}
-
-fn channels_to_color(channels: [f32; 4]) -> u32 {
-    u32::from_be_bytes(channels.map(|c| (c * 256.0) as u8))
-}
-
 struct BlendContext {
-    src_alpha: f32,
-    dst_alpha: f32,
+    src: [f32; 4],
+    dst: [f32; 4],
+    src1: [f32; 4],
 }
 
 impl ComponentBlendOp {
-    fn channel_term(value: f32, factor: &BlendFactor, context: &BlendContext) -> f32 {
+    fn channel_term(
+        value: f32,
+        channel: usize,
+        factor: &BlendFactor,
+        context: &BlendContext,
+    ) -> f32 {
         let coeff = match factor {
             BlendFactor::Zero => 0.0,
             BlendFactor::One => 1.0,
-            BlendFactor::SrcAlpha => context.src_alpha,
-            BlendFactor::OneMinusSrcAlpha => 1.0 - context.src_alpha,
-            BlendFactor::DstAlpha => context.dst_alpha,
-            BlendFactor::OneMinusDstAlpha => 1.0 - context.dst_alpha,
+            BlendFactor::SrcAlpha => context.src[3],
+            BlendFactor::OneMinusSrcAlpha => 1.0 - context.src[3],
+            BlendFactor::DstAlpha => context.dst[3],
+            BlendFactor::OneMinusDstAlpha => 1.0 - context.dst[3],
+            BlendFactor::SrcColor => context.src[channel],
+            BlendFactor::OneMinusSrcColor => 1.0 - context.src[channel],
+            BlendFactor::DstColor => context.dst[channel],
+            BlendFactor::OneMinusDstColor => 1.0 - context.dst[channel],
+            BlendFactor::Src1Color => context.src1[channel],
+            BlendFactor::OneMinusSrc1Color => 1.0 - context.src1[channel],
+            BlendFactor::Src1Alpha => context.src1[3],
+            BlendFactor::OneMinusSrc1Alpha => 1.0 - context.src1[3],
         };
 
         coeff * value
     }
 
-    fn blend(&self, src: f32, dst: f32, context: &BlendContext) -> f32 {
-        let src_term = Self::channel_term(src, &self.src_factor, context);
-        let dst_term = Self::channel_term(dst, &self.dst_factor, context);
+    // true if a source with zero alpha leaves the destination color as it was, as with src-over
+    fn ignores_transparent_src(&self) -> bool {
+        matches!(self.src_factor, BlendFactor::Zero | BlendFactor::SrcAlpha)
+            && matches!(
+                self.dst_factor,
+                BlendFactor::One | BlendFactor::OneMinusSrcAlpha
+            )
+            && matches!(self.op, BlendOp::Add | BlendOp::DstSubSrc)
+    }
+
+    fn blend(&self, src: f32, dst: f32, channel: usize, context: &BlendContext) -> f32 {
+        let src_term = Self::channel_term(src, channel, &self.src_factor, context);
+        let dst_term = Self::channel_term(dst, channel, &self.dst_factor, context);
 
         match &self.op {
             BlendOp::Add => src_term + dst_term,
             BlendOp::SrcSubDst => src_term - dst_term,
             BlendOp::DstSubSrc => dst_term - src_term,
+            BlendOp::Min => src.min(dst),
+            BlendOp::Max => src.max(dst),
         }
     }
 }
 
 impl BlendAttachment {
-    fn blend_colors(&self, src: u32, dst: u32) -> u32 {
-        let src_channels = color_to_channels(src);
-        let dst_channels = color_to_channels(dst);
+    // writes the source color as-is, for attachments that shouldn't blend in a pipeline that does
+    pub fn disabled() -> BlendAttachment {
+        BlendAttachment {
+            color: None,
+            alpha: None,
+        }
+    }
+
+    fn blend_colors(&self, src: u32, src1: Option<u32>, dst: u32, format: PixelFormat) -> u32 {
+        if self.color.is_none() && self.alpha.is_none() {
+            return src;
+        }
+
+        let src_channels = format.decode(src);
+        let dst_channels = format.decode(dst);
 
         let context = BlendContext {
-            src_alpha: src_channels[3],
-            dst_alpha: dst_channels[3],
+            src: src_channels,
+            dst: dst_channels,
+            src1: src1.map_or([0.0; 4], |color| format.decode(color)),
         };
 
-        channels_to_color(array::from_fn(|i| {
+        format.encode(array::from_fn(|i| {
             let component_op = match i {
                 3 => &self.alpha,
                 _ => &self.color,
             };
 
             match component_op {
-                Some(op) => op.blend(src_channels[i], dst_channels[i], &context),
+                Some(op) => op.blend(src_channels[i], dst_channels[i], i, &context),
                 None => src_channels[i],
             }
         }))
@@ -149,21 +260,123 @@ impl DepthMode {
     }
 }
 
+// how a fragment's depth is compared against the stored depth. the fragment passes if
+// `fragment <op> stored` holds
+#[derive(Debug, Clone, Copy)]
+pub enum DepthCompare {
+    Never,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+    Always,
+}
+
+impl DepthCompare {
+    fn passes(&self, depth: f32, stored: f32) -> bool {
+        match self {
+            DepthCompare::Never => false,
+            DepthCompare::Less => depth < stored,
+            DepthCompare::LessOrEqual => depth <= stored,
+            DepthCompare::Equal => depth == stored,
+            DepthCompare::NotEqual => depth != stored,
+            DepthCompare::GreaterOrEqual => depth >= stored,
+            DepthCompare::Greater => depth > stored,
+            DepthCompare::Always => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum WindingOrder {
     Clockwise,
     CounterClockwise,
 }
 
-#[derive(Debug)]
+impl WindingOrder {
+    pub fn flipped(&self) -> WindingOrder {
+        match self {
+            WindingOrder::Clockwise => WindingOrder::CounterClockwise,
+            WindingOrder::CounterClockwise => WindingOrder::Clockwise,
+        }
+    }
+}
+
+// what happens to fragments shaded with an alpha of zero when every color attachment blends
+// src-over-like, so writing them wouldn't change any color anyway. only the color ops are
+// checked, so the destination alpha is kept even where the alpha op would have changed it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaZeroDiscard {
+    #[default]
+    Off,
+
+    // skips the color writes but still writes depth if the pipeline does
+    ColorOnly,
+    ColorAndDepth,
+}
+
+// how line topologies are rasterized. antialiased lines use xiaolin wu's algorithm, shading the
+// two pixels straddling the line at every step along its major axis. each fragment's alpha is
+// multiplied by how much of it the line covers, so they only look smooth with blending enabled
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineRasterization {
+    #[default]
+    Aliased,
+    Antialiased,
+}
+
+// which corner of the framebuffer NDC (-1, -1) maps to. viewports and scissors are always in
+// framebuffer pixels from the top left, only the geometry inside them is flipped
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    #[default]
+    TopLeft,
+
+    // opengl style, NDC +Y points up the framebuffer rows
+    BottomLeft,
+}
+
+#[derive(Debug, Clone)]
 pub struct Pipeline<T: Shader> {
+    pub topology: Topology,
     pub depth: DepthMode,
+    pub depth_compare: DepthCompare,
 
+    // without culling both sides are drawn with the same interpolated values, and
+    // FragmentContext::front_facing tells them apart, e.g. to flip normals on two-sided materials
     pub cull_back: bool,
     pub winding_order: WindingOrder,
 
     pub blending: Option<Vec<BlendAttachment>>,
 
+    // triangles covering less than this many pixels are skipped entirely
+    pub min_triangle_area: Option<f32>,
+
+    // clamps interpolation weights to [0, 1] and renormalizes them before blending vertex outputs,
+    // so float error at the edges can't push attributes past the values at the vertices
+    pub clamp_barycentric: bool,
+
+    // fragments whose shaded rgb matches the rgb of this color are discarded before blending and
+    // depth writes. alpha is ignored
+    pub color_key: Option<u32>,
+
+    // width and height in pixels of the quads drawn by Topology::PointList
+    pub point_size: f32,
+
+    // passes the destination color to the fragment stage for blending in the shader. the value
+    // depends on every fragment written to the pixel before it, so output relies on faces being
+    // shaded in submission order and these pipelines can't be split up any further than that
+    pub read_dst: bool,
+
+    pub alpha_zero_discard: AlphaZeroDiscard,
+    pub line_rasterization: LineRasterization,
+
+    // triangles are split into tessellation_level² smaller ones before rasterizing, see
+    // Shader::tessellate_vertex. 0 and 1 both leave them as they are
+    pub tessellation_level: u32,
+
     pub shader: T,
 }
 
@@ -176,96 +389,368 @@ pub struct IndexedRenderCall<'a, T: Shader> {
 
     pub scissor: Option<Scissor>,
 
+    // bound faces by the scissor alone instead of also computing their bounding boxes, which only
+    // pays off when the scissor is already about as tight. the output is the same either way,
+    // pixels between the box and the scissor fail the edge tests instead
+    pub use_user_scissor_only: bool,
+
+    // confines the entire call to a region of the framebuffer. faces are clipped to it, and the
+    // default viewport covers it instead of the whole framebuffer
+    pub render_area: Option<Scissor>,
+
+    // an empty slice renders to a single viewport covering the render area, or the entire
+    // framebuffer if there is none
+    pub viewports: &'a [Viewport],
+
     pub indices: &'a [u16],
+
+    // number of vertices the shader can fetch. when set, indices are checked against it before
+    // any vertex is shaded, unless the rasterizer trusts its input. the rasterizer can't know how
+    // many vertices the uniforms hold, so when this is None nothing is checked and an out of range
+    // index still panics in the vertex stage
+    pub vertex_count: Option<usize>,
+
+    // index value that ends the current strip, fan or loop and starts a new one. indices are u16,
+    // so this is usually 0xFFFF (the equivalent of 0xFFFFFFFF for u32 index buffers)
+    pub primitive_restart: Option<u16>,
+
+    // swaps the pipeline's winding order for this call, for meshes whose transform mirrors them.
+    // see flips_winding
+    pub flip_winding: bool,
+
+    // planes as (a, b, c, d), keeping the side where ax + by + cz + d >= 0. tested against the
+    // positions from Shader::clip_position, so they live in whatever space the shader returns
+    pub clip_planes: &'a [Vector4<f32>],
+
+    // depth tested against instead of the render target's depth attachment, e.g. the scene depth
+    // when drawing decals. it is never written to, DepthMode::Write still writes to the target.
+    // must be the same size as the render target
+    pub depth_source: Option<&'a Image<f32>>,
+
     pub data: &'a T::Uniform,
 }
 
-pub fn gen_scissor(uv: &[Point2<f32>], max_width: usize, max_height: usize) -> Scissor {
-    let mut x0 = max_width;
-    let mut y0 = max_height;
+impl<'a, T: Shader> IndexedRenderCall<'a, T> {
+    // single instance covering the whole framebuffer, see IndexedRenderCallBuilder for the rest
+    pub fn builder(
+        pipeline: &'a Pipeline<T>,
+        indices: &'a [u16],
+        data: &'a T::Uniform,
+    ) -> IndexedRenderCallBuilder<'a, T> {
+        IndexedRenderCallBuilder {
+            call: IndexedRenderCall {
+                pipeline,
+                vertex_offset: 0,
+                first_instance: 0,
+                instance_count: 1,
+                scissor: None,
+                use_user_scissor_only: false,
+                render_area: None,
+                viewports: &[],
+                indices,
+                vertex_count: None,
+                primitive_restart: None,
+                flip_winding: false,
+                clip_planes: &[],
+                depth_source: None,
+                data,
+            },
+        }
+    }
 
-    let mut x1: usize = 0;
-    let mut y1: usize = 0;
+    // winding order of front faces for this call
+    fn winding_order(&self) -> WindingOrder {
+        match self.flip_winding {
+            true => self.pipeline.winding_order.flipped(),
+            false => self.pipeline.winding_order,
+        }
+    }
+}
 
-    for point in uv {
-        let x = point.x.clamp(0.0, 1.0) * max_width as f32;
-        let y = point.y.clamp(0.0, 1.0) * max_height as f32;
+pub struct IndexedRenderCallBuilder<'a, T: Shader> {
+    call: IndexedRenderCall<'a, T>,
+}
 
-        x0 = (x.floor() as usize).min(x0);
-        y0 = (y.floor() as usize).min(y0);
+impl<'a, T: Shader> IndexedRenderCallBuilder<'a, T> {
+    pub fn instances(mut self, count: usize) -> Self {
+        self.call.instance_count = count;
+        self
+    }
 
-        x1 = (x.ceil() as usize).max(x1);
-        y1 = (y.ceil() as usize).max(y1);
+    pub fn first_instance(mut self, first: usize) -> Self {
+        self.call.first_instance = first;
+        self
     }
 
-    Scissor {
-        x: x0,
-        y: y0,
-        width: x1 - x0,
-        height: y1 - y0,
+    pub fn vertex_offset(mut self, offset: usize) -> Self {
+        self.call.vertex_offset = offset;
+        self
+    }
+
+    pub fn scissor(mut self, scissor: Scissor) -> Self {
+        self.call.scissor = Some(scissor);
+        self
+    }
+
+    pub fn use_user_scissor_only(mut self, enabled: bool) -> Self {
+        self.call.use_user_scissor_only = enabled;
+        self
+    }
+
+    pub fn render_area(mut self, render_area: Scissor) -> Self {
+        self.call.render_area = Some(render_area);
+        self
+    }
+
+    pub fn viewports(mut self, viewports: &'a [Viewport]) -> Self {
+        self.call.viewports = viewports;
+        self
+    }
+
+    pub fn vertex_count(mut self, count: usize) -> Self {
+        self.call.vertex_count = Some(count);
+        self
+    }
+
+    pub fn primitive_restart(mut self, index: u16) -> Self {
+        self.call.primitive_restart = Some(index);
+        self
+    }
+
+    pub fn flip_winding(mut self, flip: bool) -> Self {
+        self.call.flip_winding = flip;
+        self
+    }
+
+    pub fn clip_planes(mut self, planes: &'a [Vector4<f32>]) -> Self {
+        self.call.clip_planes = planes;
+        self
+    }
+
+    pub fn depth_source(mut self, depth: &'a Image<f32>) -> Self {
+        self.call.depth_source = Some(depth);
+        self
+    }
+
+    pub fn build(self) -> IndexedRenderCall<'a, T> {
+        self.call
     }
 }
 
-fn rotate_cw(v: &Vector2<f32>) -> Vector2<f32> {
-    Vector2::new(-v.y, v.x)
+// owned equivalent of IndexedRenderCall, which can be moved to another thread
+pub struct AsyncRenderCall<T: Shader> {
+    pub pipeline: Arc<Pipeline<T>>,
+
+    pub vertex_offset: usize,
+    pub first_instance: usize,
+    pub instance_count: usize,
+
+    pub scissor: Option<Scissor>,
+    pub use_user_scissor_only: bool,
+    pub render_area: Option<Scissor>,
+    pub viewports: Vec<Viewport>,
+
+    pub indices: Vec<u16>,
+    pub vertex_count: Option<usize>,
+    pub primitive_restart: Option<u16>,
+    pub flip_winding: bool,
+    pub clip_planes: Vec<Vector4<f32>>,
+
+    // shared so the scene depth doesn't have to be copied for every call. only ever read
+    pub depth_source: Option<Arc<Image<f32>>>,
+
+    pub data: Arc<T::Uniform>,
+}
+
+pub struct RenderHandle {
+    thread: JoinHandle<Result<RenderStats, RasterizerError>>,
 }
 
-fn rotate_ccw(v: &Vector2<f32>) -> Vector2<f32> {
-    Vector2::new(v.y, -v.x)
+impl RenderHandle {
+    // blocks until the render has finished and merges its stats into the rasterizer. a panic on
+    // the render thread leaves the framebuffer's mutex poisoned
+    pub fn join(self, rasterizer: &mut Rasterizer) -> Result<(), RasterizerError> {
+        let stats = self
+            .thread
+            .join()
+            .map_err(|_| RasterizerError::RenderThreadPanicked)??;
+        rasterizer.stats.merge(&stats);
+
+        Ok(())
+    }
 }
 
-fn signed_triangle_area(points: [&Point2<f32>; 3], winding: WindingOrder) -> f32 {
-    let a = points[0];
-    let b = points[1];
-    let c = points[2];
+// pixels are sampled at their centers, so the scissor only includes rows and columns whose
+// center lies within the bounds of the face. +Y is down in both NDC and the framebuffer
+pub fn gen_scissor(uv: &[Point2<f32>], max_width: usize, max_height: usize) -> Scissor {
+    let mut min = Point2::new(1.0f32, 1.0);
+    let mut max = Point2::new(0.0f32, 0.0);
+
+    for point in uv {
+        let clamped = point.map(|x| x.clamp(0.0, 1.0));
 
-    let ab = b - a;
-    let ac = c - a;
+        min = min.inf(&clamped);
+        max = max.sup(&clamped);
+    }
 
-    let normal = match winding {
-        // rotate counterclockwise 90 deg
-        WindingOrder::CounterClockwise => rotate_ccw(&ab),
+    // first and one past the last pixel with a center in [low, high]
+    let pixel_range = |low: f32, high: f32, size: usize| {
+        let first = (low * size as f32 - 0.5).ceil().max(0.0) as usize;
+        let end = ((high * size as f32 - 0.5).floor() + 1.0).max(0.0) as usize;
 
-        // rotate clockwise 90 deg
-        WindingOrder::Clockwise => rotate_cw(&ab),
+        (first.min(size), end.min(size))
     };
 
-    ac.dot(&normal) / 2.0
+    let (x0, x1) = pixel_range(min.x, max.x, max_width);
+    let (y0, y1) = pixel_range(min.y, max.y, max_height);
+
+    Scissor {
+        x: x0,
+        y: y0,
+        width: x1.saturating_sub(x0),
+        height: y1.saturating_sub(y0),
+    }
 }
 
 pub const VERTICES_PER_FACE: usize = 3;
+pub const VERTICES_PER_LINE: usize = 2;
+pub const VERTICES_PER_POINT: usize = 1;
+
+// uv coordinates of the two triangles making up a point quad, wound clockwise on screen
+const POINT_QUAD: [(f32, f32); 6] = [
+    (0.0, 0.0),
+    (1.0, 0.0),
+    (0.0, 1.0),
+    (1.0, 0.0),
+    (1.0, 1.0),
+    (0.0, 1.0),
+];
+
+// expands a point into the outputs of two triangles covering a size pixel square around it
+fn expand_point<T: Shader>(
+    call: &IndexedRenderCall<T>,
+    point: &VertexOutput<T::Working>,
+    size: f32,
+    viewport: &Viewport,
+    quad: &mut Vec<VertexOutput<T::Working>>,
+) {
+    let pipeline = call.pipeline;
+    let half_extent = Point2::new(size / viewport.width as f32, size / viewport.height as f32);
+
+    quad.extend((0..POINT_QUAD.len()).map(|i| {
+        // swap the last two vertices of each triangle to keep them front facing
+        let corner = match (call.winding_order(), i % VERTICES_PER_FACE) {
+            (WindingOrder::CounterClockwise, 1) => i + 1,
+            (WindingOrder::CounterClockwise, 2) => i - 1,
+            _ => i,
+        };
+
+        let (u, v) = POINT_QUAD[corner];
+        let uv = Point2::new(u, v);
+
+        // blending a single output with a weight of 1 copies it
+        let working = T::Working::blend(&[&point.data], &[1.0]);
+
+        VertexOutput {
+            position: Point3::new(
+                point.position.x + (u * 2.0 - 1.0) * half_extent.x,
+                point.position.y + (v * 2.0 - 1.0) * half_extent.y,
+                point.position.z,
+            ),
+            data: pipeline.shader.point_corner(working, uv),
+        }
+    }));
+}
+
+// pixel bounds of the segment between two points in viewport space, grown by margin pixels on
+// every side
+fn gen_line_scissor(
+    uv: &[Point2<f32>],
+    max_width: usize,
+    max_height: usize,
+    margin: usize,
+) -> Scissor {
+    let pixels = uv.iter().map(|point| {
+        let x = point.x.clamp(0.0, 1.0) * max_width as f32;
+        let y = point.y.clamp(0.0, 1.0) * max_height as f32;
+
+        (x.floor() as usize, y.floor() as usize)
+    });
+
+    let (x0, y0, x1, y1) = pixels
+        .fold((max_width, max_height, 0, 0), |(x0, y0, x1, y1), (x, y)| {
+            (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1))
+        });
+
+    let (x0, y0) = (x0.saturating_sub(margin), y0.saturating_sub(margin));
+    let (x1, y1) = ((x1 + margin).min(max_width), (y1 + margin).min(max_height));
+
+    Scissor {
+        x: x0,
+        y: y0,
+        width: x1.saturating_sub(x0),
+        height: y1.saturating_sub(y0),
+    }
+}
 
 struct FragmentInfo {
     depth: f32,
     weights: [f32; VERTICES_PER_FACE],
+
+    // fraction of the pixel covered, only ever below 1 for antialiased lines
+    coverage: f32,
+
+    // lines and points are always front facing
+    front_facing: bool,
+}
+
+fn clamp_weights(weights: &mut [f32]) {
+    for weight in weights.iter_mut() {
+        *weight = weight.clamp(0.0, 1.0);
+    }
+
+    let sum = weights.iter().sum::<f32>();
+    if sum > 0.0 {
+        for weight in weights.iter_mut() {
+            *weight /= sum;
+        }
+    }
 }
 
 fn process_fragment_geometry<T: Shader>(
     triangle: &[Point3<f32>; VERTICES_PER_FACE],
     point: &Point2<f32>,
-    pipeline: &Pipeline<T>,
+    call: &IndexedRenderCall<T>,
 ) -> Option<FragmentInfo> {
+    let winding = call.winding_order();
     let screen_points = triangle.each_ref().map(|p| p.xy());
     let areas: [_; VERTICES_PER_FACE] = array::from_fn(|i| {
         let a = &screen_points[(i + 1) % VERTICES_PER_FACE];
         let b = &screen_points[(i + 2) % VERTICES_PER_FACE];
 
-        signed_triangle_area([a, b, point], pipeline.winding_order)
+        signed_triangle_area([a, b, point], winding)
     });
 
     // im not gonna bother trying to make this more idiomatic
     let areas_valid = areas.each_ref().map(|area| *area >= 0.0);
     let mut should_keep = areas_valid.iter().all(|valid| *valid);
 
-    if !pipeline.cull_back {
-        // if we dont cull, also keep back
-        should_keep |= areas_valid.iter().all(|valid| !*valid);
+    if !call.pipeline.cull_back {
+        // if we dont cull, also keep back. points on an edge have an area of zero either way, so
+        // back faces need their own comparison or they would lose their edges
+        should_keep |= areas.iter().all(|area| *area <= 0.0);
     }
 
-    if should_keep {
-        let area_sum = areas.iter().sum::<f32>();
+    // degenerate faces cover no area and would divide by zero below. on back faces every area is
+    // negative, and so is the sum, so the weights come out positive and the same as they would on
+    // the front face. the sign of the sum is all that tells the two sides apart
+    let area_sum = areas.iter().sum::<f32>();
+    if should_keep && area_sum != 0.0 {
         let flat_weights = areas.map(|area| area / area_sum);
 
+        // every term is computed and summed in vertex order, so the same triangle always produces
+        // bit-identical depth. this is what makes DepthCompare::Equal usable after a prepass, as
+        // long as both passes submit the vertices in the same order
         let inverse_depths = triangle.each_ref().map(|p| 1.0 / p.z);
         let inverse_depth = flat_weights
             .iter()
@@ -276,6 +761,8 @@ fn process_fragment_geometry<T: Shader>(
         Some(FragmentInfo {
             depth: 1.0 / inverse_depth,
             weights: array::from_fn(|i| flat_weights[i] * inverse_depths[i] / inverse_depth),
+            coverage: 1.0,
+            front_facing: area_sum > 0.0,
         })
     } else {
         None
@@ -283,11 +770,19 @@ fn process_fragment_geometry<T: Shader>(
 }
 
 // returns false if fragment should be discarded
-fn depth_test(x: usize, current_depth: f32, scanline: &MutableScanline) -> bool {
-    if let Some(depth) = &scanline.depth {
-        let closest_depth = depth[x];
-
-        current_depth <= closest_depth
+fn depth_test(
+    x: usize,
+    compare: &DepthCompare,
+    current_depth: f32,
+    depth_source: Option<&Image<f32>>,
+    scanline: &MutableScanline,
+) -> bool {
+    // compared at the precision it would be stored at
+    let current_depth = scanline.depth_format.quantize(current_depth);
+    if let Some(source) = depth_source {
+        compare.passes(current_depth, *source.at(x, scanline.y).unwrap())
+    } else if let Some(depth) = &scanline.depth {
+        compare.passes(current_depth, depth.get(x))
     } else {
         true
     }
@@ -296,22 +791,41 @@ fn depth_test(x: usize, current_depth: f32, scanline: &MutableScanline) -> bool
 struct FaceContext<'a, T: Shader> {
     instance_id: usize,
     call: &'a IndexedRenderCall<'a, T>,
-    vertex_output: &'a [VertexOutput<T::Working>; VERTICES_PER_FACE],
 
-    fb_width: usize,
-    fb_height: usize,
+    // either VERTICES_PER_FACE or VERTICES_PER_LINE outputs, points are expanded into triangles
+    // beforehand
+    vertex_output: &'a [VertexOutput<T::Working>],
+    viewport: &'a Viewport,
+    subpixel_bits: Option<u8>,
+}
+
+// counts every generated fragment, regardless of whether it passes the depth test
+fn count_overdraw(x: usize, scanline: &mut MutableScanline) {
+    if let Some(overdraw) = &mut scanline.overdraw {
+        overdraw[x] += 1;
+    }
 }
 
-fn should_discard_fragment(
+fn should_discard_fragment<T: Shader>(
     x: usize,
-    depth_mode: &DepthMode,
+    call: &IndexedRenderCall<T>,
+    viewport: &Viewport,
     current_depth: f32,
     scanline: &MutableScanline,
 ) -> bool {
-    if current_depth < 0.0 {
+    let pipeline = call.pipeline;
+
+    if !viewport.contains_depth(current_depth) {
         true
     } else {
-        depth_mode.should_test() && !depth_test(x, current_depth, scanline)
+        pipeline.depth.should_test()
+            && !depth_test(
+                x,
+                &pipeline.depth_compare,
+                current_depth,
+                call.depth_source,
+                scanline,
+            )
     }
 }
 
@@ -320,178 +834,1737 @@ fn render_fragment<T: Shader>(
     context: &FaceContext<T>,
     scanline: &mut MutableScanline,
     point: Point2<f32>,
-    frag: FragmentInfo,
+    mut frag: FragmentInfo,
 ) {
-    let color = context
+    // lines only fill the first two weights
+    let vertex_count = context.vertex_output.len();
+    if context.call.pipeline.clamp_barycentric {
+        clamp_weights(&mut frag.weights[..vertex_count]);
+    }
+
+    let working: [_; VERTICES_PER_FACE] =
+        array::from_fn(|i| &context.vertex_output[i.min(vertex_count - 1)].data);
+
+    // non-finite weights usually come from a vertex stage outputting a depth of zero or a
+    // non-finite position
+    let weights = &frag.weights[..vertex_count];
+    debug_assert!(
+        weights.iter().all(|weight| weight.is_finite()),
+        "Non-finite blend weights {:?}!",
+        weights
+    );
+
+    debug_assert!(
+        (weights.iter().sum::<f32>() - 1.0).abs() < 1e-3,
+        "Blend weights {:?} do not sum to 1!",
+        weights
+    );
+
+    #[allow(deprecated)]
+    let fragment_context = FragmentContext {
+        instance_id: context.instance_id,
+        position: Point3::new(point.x, point.y, frag.depth),
+        frag_coord: Point4::new(
+            x as f32 + 0.5,
+            scanline.y as f32 + 0.5,
+            context.viewport.window_depth(frag.depth),
+            1.0 / frag.depth,
+        ),
+        dst_color: match context.call.pipeline.read_dst {
+            true => scanline.color.first().map(|row| row[x]),
+            false => None,
+        },
+        front_facing: frag.front_facing,
+        data: context.call.data,
+        working: match vertex_count {
+            VERTICES_PER_FACE => T::Working::blend3(
+                working[0], weights[0], working[1], weights[1], working[2], weights[2],
+            ),
+            _ => T::Working::blend(&working[..vertex_count], weights),
+        },
+    };
+
+    let (color, src1) = context
         .call
         .pipeline
         .shader
-        .fragment_stage(&FragmentContext {
-            instance_id: context.instance_id,
-            position: Point3::new(point.x, point.y, frag.depth),
-            data: &context.call.data,
-            working: T::Working::blend(
-                &context.vertex_output.each_ref().map(|output| &output.data),
-                &frag.weights,
-            ),
-        });
+        .fragment_stage_dual(&fragment_context);
+
+    let format = scanline.color_format;
+    let color = match frag.coverage < 1.0 {
+        true => {
+            let mut channels = format.decode(color);
+            channels[3] *= frag.coverage;
+            format.encode(channels)
+        }
+        false => color,
+    };
+
+    if let Some(key) = context.call.pipeline.color_key
+        && format.rgb_bits(color) == format.rgb_bits(key)
+    {
+        return;
+    }
+
+    let transparent = match &context.call.pipeline.blending {
+        Some(blending) if context.call.pipeline.alpha_zero_discard != AlphaZeroDiscard::Off => {
+            format.decode(color)[3] == 0.0
+                && blending.iter().all(|attachment| {
+                    attachment
+                        .color
+                        .as_ref()
+                        .is_some_and(|op| op.ignores_transparent_src())
+                })
+        }
+        _ => false,
+    };
+
+    if transparent {
+        if context.call.pipeline.alpha_zero_discard == AlphaZeroDiscard::ColorOnly {
+            write_depth(context.call.pipeline, frag.depth, x, scanline);
+        }
+
+        return;
+    }
 
     for i in 0..scanline.color.len() {
         let row = &mut scanline.color[i];
 
         row[x] = match &context.call.pipeline.blending {
-            Some(blending) => blending[i].blend_colors(color, row[x]),
+            Some(blending) => blending[i].blend_colors(color, src1, row[x], format),
             None => color,
         };
     }
 
-    if context.call.pipeline.depth.should_write()
+    write_depth(context.call.pipeline, frag.depth, x, scanline);
+}
+
+fn write_depth<T: Shader>(
+    pipeline: &Pipeline<T>,
+    depth: f32,
+    x: usize,
+    scanline: &mut MutableScanline,
+) {
+    if pipeline.depth.should_write()
         && let Some(depth_row) = &mut scanline.depth
     {
-        depth_row[x] = frag.depth;
+        depth_row.set(x, depth);
     }
 }
 
-fn process_pixel<T: Shader>(x: usize, context: &FaceContext<T>, scanline: &mut MutableScanline) {
-    let point = Point2::new(
-        (((x as f32 + 0.5) / context.fb_width as f32) * 2.0) - 1.0,
-        (((scanline.y as f32 + 0.5) / context.fb_height as f32) * 2.0) - 1.0,
-    );
+// shades the pixels of a scanline in [x0, x1). the row's ndc y and the snapped vertex positions
+// dont change along the row, so theyre only computed once
+fn process_span<T: Shader>(
+    x0: usize,
+    x1: usize,
+    context: &FaceContext<T>,
+    scanline: &mut MutableScanline,
+) {
+    let viewport = context.viewport;
+    let ndc_y = viewport.pixel_y_to_ndc(scanline.y);
+    let vertex_positions = array::from_fn(|i| {
+        let position = context.vertex_output[i].position;
+        match context.subpixel_bits {
+            Some(bits) => {
+                let snapped = viewport.snap_ndc(position.xy(), bits);
+                Point3::new(snapped.x, snapped.y, position.z)
+            }
+            None => position,
+        }
+    });
 
-    let vertex_positions = context.vertex_output.each_ref().map(|data| data.position);
-    if let Some(frag) = process_fragment_geometry(&vertex_positions, &point, &context.call.pipeline)
-    {
-        if should_discard_fragment(x, &context.call.pipeline.depth, frag.depth, scanline) {
-            return;
+    for x in x0..x1 {
+        let point = Point2::new(viewport.pixel_x_to_ndc(x), ndc_y);
+        let Some(frag) = process_fragment_geometry(&vertex_positions, &point, context.call) else {
+            continue;
+        };
+
+        count_overdraw(x, scanline);
+        if should_discard_fragment(x, context.call, context.viewport, frag.depth, scanline) {
+            continue;
         }
 
         render_fragment(x, context, scanline, point, frag);
     }
 }
 
-#[derive(Debug, Default)]
-pub struct RenderStats {
-    pub faces_processed: usize,
-    pub faces_rendered: usize,
-    pub instances: usize,
-    pub calls: usize,
-}
+fn line_fragment(line: &[Point3<f32>; VERTICES_PER_LINE], t: f32) -> FragmentInfo {
+    let flat_weights = [1.0 - t, t];
 
-pub struct Rasterizer {
-    stats: RenderStats,
-    render_targets: LinkedList<Arc<Mutex<Framebuffer>>>,
-}
+    let inverse_depths = line.each_ref().map(|p| 1.0 / p.z);
+    let inverse_depth = flat_weights[0] * inverse_depths[0] + flat_weights[1] * inverse_depths[1];
 
-impl Rasterizer {
-    pub fn new() -> Rasterizer {
-        Rasterizer {
-            stats: RenderStats::default(),
-            render_targets: LinkedList::new(),
-        }
+    FragmentInfo {
+        depth: 1.0 / inverse_depth,
+        weights: array::from_fn(|i| match i {
+            0 | 1 => flat_weights[i] * inverse_depths[i] / inverse_depth,
+            _ => 0.0,
+        }),
+        coverage: 1.0,
+        front_facing: true,
     }
+}
 
-    pub fn new_frame(&mut self) -> Result<(), RasterizerError> {
-        if !self.render_targets.is_empty() {
-            Err(RasterizerError::RenderTargetUnfinished)
-        } else {
-            self.stats = RenderStats::default();
-            Ok(())
-        }
-    }
+// walks the segment one pixel at a time. scanlines must be a contiguous run of rows, pixels
+// outside of them or the scissor are skipped
+fn process_line<T: Shader>(
+    context: &FaceContext<T>,
+    scissor: &Scissor,
+    scanlines: &mut [MutableScanline],
+) {
+    let Some(first_row) = scanlines.first().map(|scanline| scanline.y) else {
+        return;
+    };
 
-    pub fn stats<'a>(&'a self) -> &'a RenderStats {
-        &self.stats
-    }
+    let viewport = context.viewport;
+    let positions: [_; VERTICES_PER_LINE] = array::from_fn(|i| context.vertex_output[i].position);
+    let ends = positions.each_ref().map(|p| {
+        Point2::new(
+            viewport.x as f32 + (p.x + 1.0) / 2.0 * viewport.width as f32,
+            viewport.y as f32 + (p.y + 1.0) / 2.0 * viewport.height as f32,
+        )
+    });
 
-    pub fn push_render_target(&mut self, target: Arc<Mutex<Framebuffer>>) {
-        self.render_targets.push_back(target);
+    if context.call.pipeline.line_rasterization == LineRasterization::Antialiased {
+        process_line_antialiased(context, &positions, &ends, scissor, scanlines);
+        return;
     }
 
-    pub fn pop_render_target(&mut self) -> Result<(), RasterizerError> {
-        match self.render_targets.pop_back() {
-            Some(_) => Ok(()),
-            None => Err(RasterizerError::NoRenderTarget),
+    let delta = ends[1] - ends[0];
+    let steps = delta.x.abs().max(delta.y.abs()).ceil().max(1.0) as usize;
+
+    let mut last_pixel = None;
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let pixel = ends[0] + delta * t;
+
+        if pixel.x < 0.0 || pixel.y < 0.0 {
+            continue;
         }
-    }
 
-    pub fn current_render_target(&mut self) -> Result<Arc<Mutex<Framebuffer>>, RasterizerError> {
-        match self.render_targets.back() {
-            Some(top) => Ok(top.clone()),
-            None => Err(RasterizerError::NoRenderTarget),
+        // dont shade the same pixel twice when the segment doesnt advance a full pixel per step
+        let (x, y) = (pixel.x as usize, pixel.y as usize);
+        if last_pixel == Some((x, y)) {
+            continue;
         }
+
+        last_pixel = Some((x, y));
+        let frag = line_fragment(&positions, t);
+        process_line_pixel(context, x, y, frag, scissor, scanlines, first_row);
     }
+}
 
-    fn render_face<T: Shader + Sync>(
-        &mut self,
-        instance_id: usize,
-        face_index: usize,
-        call: &IndexedRenderCall<T>,
-        framebuffer: &mut Framebuffer,
-    ) {
-        let index_offset = face_index * VERTICES_PER_FACE;
-        let (fb_width, fb_height) = framebuffer.size();
+// ends are the endpoints in framebuffer pixels. every pixel whose center along the major axis lies
+// within the segment is paired with its neighbour on the minor axis, and the line's distance from
+// their centers splits the coverage between them. the ends aren't faded out, so segments of a
+// strip join without a gap or a double blended pixel
+fn process_line_antialiased<T: Shader>(
+    context: &FaceContext<T>,
+    positions: &[Point3<f32>; VERTICES_PER_LINE],
+    ends: &[Point2<f32>; VERTICES_PER_LINE],
+    scissor: &Scissor,
+    scanlines: &mut [MutableScanline],
+) {
+    let first_row = scanlines[0].y;
+    let delta = ends[1] - ends[0];
+
+    // walk along whichever axis the line moves further on, with (major, minor) coordinates
+    let steep = delta.y.abs() > delta.x.abs();
+    let [(major0, minor0), (major1, minor1)] = ends.map(|end| match steep {
+        true => (end.y, end.x),
+        false => (end.x, end.y),
+    });
+
+    // zero length lines cover nothing
+    if major0 == major1 {
+        return;
+    }
+
+    let gradient = (minor1 - minor0) / (major1 - major0);
+    let first = (major0.min(major1) - 0.5).ceil().max(0.0) as usize;
+    let last = (major0.max(major1) - 0.5).floor();
+
+    if last < 0.0 {
+        return;
+    }
+
+    for major in first..=last as usize {
+        let center = major as f32 + 0.5;
+        let t = (center - major0) / (major1 - major0);
+
+        // minor position of the line relative to the pixel centers
+        let minor = minor0 + gradient * (center - major0) - 0.5;
+        let below = minor.floor();
+        let fraction = minor - below;
+
+        for (minor, coverage) in [(below, 1.0 - fraction), (below + 1.0, fraction)] {
+            if coverage <= 0.0 || minor < 0.0 {
+                continue;
+            }
+
+            let (x, y) = match steep {
+                true => (minor as usize, major),
+                false => (major, minor as usize),
+            };
+
+            let frag = FragmentInfo {
+                coverage,
+                ..line_fragment(positions, t)
+            };
+
+            process_line_pixel(context, x, y, frag, scissor, scanlines, first_row);
+        }
+    }
+}
+
+// pixels outside of the scissor or the scanlines are skipped
+fn process_line_pixel<T: Shader>(
+    context: &FaceContext<T>,
+    x: usize,
+    y: usize,
+    frag: FragmentInfo,
+    scissor: &Scissor,
+    scanlines: &mut [MutableScanline],
+    first_row: usize,
+) {
+    if !scissor.contains(x, y) || y < first_row || y - first_row >= scanlines.len() {
+        return;
+    }
+
+    let scanline = &mut scanlines[y - first_row];
+
+    count_overdraw(x, scanline);
+    if should_discard_fragment(x, context.call, context.viewport, frag.depth, scanline) {
+        return;
+    }
+
+    let point = context.viewport.pixel_to_ndc(x, y);
+    render_fragment(x, context, scanline, point, frag);
+}
+
+fn shade_primitive<T: Shader>(
+    instance_id: usize,
+    instance: &InstanceCache,
+    primitive: &Primitive,
+    call: &IndexedRenderCall<T>,
+    vertex_output: &mut Vec<VertexOutput<T::Working>>,
+) {
+    vertex_output.extend(primitive.offsets().iter().map(|offset| {
+        call.pipeline.shader.vertex_stage(&VertexContext {
+            vertex_id: call.indices[*offset] as usize,
+            instance_id: instance_id,
+            instance,
+            data: call.data,
+        })
+    }));
+}
+
+struct ClipVertex<W> {
+    clip_position: Point3<f32>,
+    output: VertexOutput<W>,
+}
+
+fn copy_output<W: Blendable>(output: &VertexOutput<W>) -> VertexOutput<W> {
+    VertexOutput {
+        position: output.position,
+        data: W::blend(&[&output.data], &[1.0]),
+    }
+}
+
+// the point t of the way from a to b. positions are interpolated in homogeneous space, where the
+// clip positions map to them linearly, and then divided again
+fn lerp_clip_vertex<W: Blendable>(a: &ClipVertex<W>, b: &ClipVertex<W>, t: f32) -> ClipVertex<W> {
+    let (pa, pb) = (&a.output.position, &b.output.position);
+    let w = pa.z + (pb.z - pa.z) * t;
+    let x = pa.x * pa.z + (pb.x * pb.z - pa.x * pa.z) * t;
+    let y = pa.y * pa.z + (pb.y * pb.z - pa.y * pa.z) * t;
+
+    ClipVertex {
+        clip_position: a.clip_position.lerp(&b.clip_position, t),
+        output: VertexOutput {
+            position: Point3::new(x / w, y / w, w),
+            data: W::blend(&[&a.output.data, &b.output.data], &[1.0 - t, t]),
+        },
+    }
+}
+
+// cuts a shaded primitive against the call's clip planes. whatever is left is appended to clipped
+// as primitives with the same vertex count, so a triangle may come out as several triangles.
+// primitives with a vertex the shader gives no clip position for are passed through untouched
+fn clip_primitive<T: Shader>(
+    call: &IndexedRenderCall<T>,
+    vertex_output: &[VertexOutput<T::Working>],
+    clipped: &mut Vec<VertexOutput<T::Working>>,
+) {
+    let shader = &call.pipeline.shader;
+    let clip_positions: Option<Vec<_>> = vertex_output
+        .iter()
+        .map(|output| shader.clip_position(&output.data))
+        .collect();
+
+    let Some(clip_positions) = clip_positions else {
+        clipped.extend(vertex_output.iter().map(copy_output));
+        return;
+    };
+
+    let mut polygon: Vec<_> = clip_positions
+        .into_iter()
+        .zip(vertex_output)
+        .map(|(clip_position, output)| ClipVertex {
+            clip_position,
+            output: copy_output(output),
+        })
+        .collect();
+
+    for plane in call.clip_planes {
+        let distance = |vertex: &ClipVertex<T::Working>| {
+            plane.xyz().dot(&vertex.clip_position.coords) + plane.w
+        };
+
+        // a line is an open polygon, so it has no closing edge from its last vertex to its first
+        let edge_count = match vertex_output.len() {
+            VERTICES_PER_LINE => polygon.len().saturating_sub(1),
+            _ => polygon.len(),
+        };
+
+        let mut next = Vec::with_capacity(polygon.len() + 1);
+        for i in 0..edge_count {
+            let from = &polygon[i];
+            let to = &polygon[(i + 1) % polygon.len()];
+            let (d_from, d_to) = (distance(from), distance(to));
+
+            // open polygons keep their first vertex, closed ones get it from the closing edge
+            if i == 0 && edge_count < polygon.len() && d_from >= 0.0 {
+                next.push(lerp_clip_vertex(from, to, 0.0));
+            }
+
+            if (d_from >= 0.0) != (d_to >= 0.0) {
+                next.push(lerp_clip_vertex(from, to, d_from / (d_from - d_to)));
+            }
+
+            if d_to >= 0.0 {
+                next.push(lerp_clip_vertex(from, to, 1.0));
+            }
+        }
+
+        polygon = next;
+    }
+
+    match vertex_output.len() {
+        VERTICES_PER_FACE if polygon.len() >= VERTICES_PER_FACE => {
+            for i in 1..polygon.len() - 1 {
+                for vertex in [&polygon[0], &polygon[i], &polygon[i + 1]] {
+                    clipped.push(copy_output(&vertex.output));
+                }
+            }
+        }
+        count if count == polygon.len() && count < VERTICES_PER_FACE => {
+            clipped.extend(polygon.iter().map(|vertex| copy_output(&vertex.output)));
+        }
+        _ => (),
+    }
+}
+
+// splits a triangle into level² smaller ones, appending their vertices to faces. the new vertices
+// are interpolated in homogeneous space like clipped ones, so the smaller triangles cover exactly
+// the same pixels, and then passed through Shader::tessellate_vertex
+fn tessellate_face<T: Shader>(
+    call: &IndexedRenderCall<T>,
+    face: &[VertexOutput<T::Working>],
+    faces: &mut Vec<VertexOutput<T::Working>>,
+) {
+    let level = call.pipeline.tessellation_level as usize;
+    let data: Vec<_> = face.iter().map(|output| &output.data).collect();
+
+    // vertices of the subdivided triangle, row by row from the first edge
+    let mut grid = Vec::with_capacity((level + 1) * (level + 2) / 2);
+    for j in 0..=level {
+        for i in 0..=level - j {
+            let weights = [level - i - j, i, j].map(|n| n as f32 / level as f32);
+
+            let mut homogeneous = Vector4::zeros();
+            for (output, weight) in face.iter().zip(weights) {
+                let position = &output.position;
+                homogeneous += Vector4::new(
+                    position.x * position.z,
+                    position.y * position.z,
+                    position.z,
+                    0.0,
+                ) * weight;
+            }
+
+            let output = VertexOutput {
+                position: Point3::new(
+                    homogeneous.x / homogeneous.z,
+                    homogeneous.y / homogeneous.z,
+                    homogeneous.z,
+                ),
+                data: T::Working::blend(&data, &weights),
+            };
+
+            grid.push(
+                call.pipeline
+                    .shader
+                    .tessellate_vertex(output, Point3::from(weights)),
+            );
+        }
+    }
+
+    let index = |i: usize, j: usize| j * (level + 1) - j * j.saturating_sub(1) / 2 + i;
+    for j in 0..level {
+        for i in 0..level - j {
+            let upward = [index(i, j), index(i + 1, j), index(i, j + 1)];
+            faces.extend(upward.map(|vertex| copy_output(&grid[vertex])));
+
+            // every upward triangle but the last in a row is followed by a downward one
+            if i + 1 < level - j {
+                let downward = [index(i + 1, j), index(i + 1, j + 1), index(i, j + 1)];
+                faces.extend(downward.map(|vertex| copy_output(&grid[vertex])));
+            }
+        }
+    }
+}
+
+// replaces the primitive shaded into outputs[offset..] with the faces that are actually drawn,
+// after clipping and tessellation. faces keep the primitive's vertex count
+fn clip_and_tessellate<T: Shader>(
+    call: &IndexedRenderCall<T>,
+    outputs: &mut Vec<VertexOutput<T::Working>>,
+    offset: usize,
+) {
+    let vertex_count = outputs.len() - offset;
+    if !call.clip_planes.is_empty() {
+        let shaded = outputs.split_off(offset);
+        clip_primitive(call, &shaded, outputs);
+    }
+
+    if call.pipeline.tessellation_level > 1 && vertex_count == VERTICES_PER_FACE {
+        let faces = outputs.split_off(offset);
+        for face in faces.chunks_exact(VERTICES_PER_FACE) {
+            tessellate_face(call, face, outputs);
+        }
+    }
+}
+
+fn instance_culled<T: Shader>(call: &IndexedRenderCall<T>, instance_id: usize) -> bool {
+    call.pipeline
+        .shader
+        .instance_bounds(instance_id, call.data)
+        .is_some_and(|bounds| bounds.is_outside())
+}
+
+fn default_viewport<T: Shader>(
+    call: &IndexedRenderCall<T>,
+    fb_width: usize,
+    fb_height: usize,
+) -> Viewport {
+    match &call.render_area {
+        Some(render_area) => Viewport {
+            x: render_area.x,
+            y: render_area.y,
+            ..Viewport::full(render_area.width, render_area.height)
+        },
+        None => Viewport::full(fb_width, fb_height),
+    }
+}
+
+fn union_scissors(a: Option<Scissor>, b: Option<Scissor>) -> Option<Scissor> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union_with(&b)),
+        (a, b) => a.or(b),
+    }
+}
+
+struct BinnedFace<'a> {
+    instance_id: usize,
+
+    // position of the face in the order it was submitted in. tiles have to walk faces in this
+    // order, or overlapping translucent faces blend differently than they would in render_indexed
+    submission: usize,
+
+    // range of the primitive's outputs in the shared vertex output buffer
+    vertex_offset: usize,
+    vertex_count: usize,
+
+    viewport: &'a Viewport,
+    scissor: Scissor,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    pub faces_processed: usize,
+    pub faces_rendered: usize,
+    pub faces_culled_small: usize,
+
+    // faces whose bounds contain no pixel centers, e.g. slivers between two rows
+    pub faces_empty: usize,
+
+    pub instances: usize,
+
+    // instances skipped entirely because Shader::instance_bounds was off screen
+    pub instances_culled: usize,
+
+    pub viewport_instances: usize,
+    pub calls: usize,
+}
+
+impl RenderStats {
+    // every counter with its field name, in declaration order
+    fn fields(&self) -> [(&'static str, usize); 8] {
+        [
+            ("faces_processed", self.faces_processed),
+            ("faces_rendered", self.faces_rendered),
+            ("faces_culled_small", self.faces_culled_small),
+            ("faces_empty", self.faces_empty),
+            ("instances", self.instances),
+            ("instances_culled", self.instances_culled),
+            ("viewport_instances", self.viewport_instances),
+            ("calls", self.calls),
+        ]
+    }
+}
+
+// one counter per line, e.g. "12 faces rendered"
+impl Display for RenderStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.fields().iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{} {}", value, name.replace('_', " "))?;
+        }
+
+        Ok(())
+    }
+}
+
+// serialized as a flat map of the field names to their counts
+#[cfg(feature = "serde")]
+impl serde::Serialize for RenderStats {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let fields = self.fields();
+        let mut state = serializer.serialize_struct("RenderStats", fields.len())?;
+        for (name, value) in fields {
+            state.serialize_field(name, &value)?;
+        }
+
+        state.end()
+    }
+}
+
+// RenderStats that can be counted from several threads at once. the counters are independent, so
+// a snapshot taken while rendering may be partway through a face
+#[derive(Debug, Default)]
+pub struct AtomicRenderStats {
+    faces_processed: AtomicUsize,
+    faces_rendered: AtomicUsize,
+    faces_culled_small: AtomicUsize,
+    faces_empty: AtomicUsize,
+    instances: AtomicUsize,
+    instances_culled: AtomicUsize,
+    viewport_instances: AtomicUsize,
+    calls: AtomicUsize,
+}
+
+fn add_stat(counter: &AtomicUsize, amount: usize) {
+    counter.fetch_add(amount, Ordering::Relaxed);
+}
+
+impl AtomicRenderStats {
+    pub fn merge(&self, other: &RenderStats) {
+        add_stat(&self.faces_processed, other.faces_processed);
+        add_stat(&self.faces_rendered, other.faces_rendered);
+        add_stat(&self.faces_culled_small, other.faces_culled_small);
+        add_stat(&self.faces_empty, other.faces_empty);
+        add_stat(&self.instances, other.instances);
+        add_stat(&self.instances_culled, other.instances_culled);
+        add_stat(&self.viewport_instances, other.viewport_instances);
+        add_stat(&self.calls, other.calls);
+    }
+
+    pub fn snapshot(&self) -> RenderStats {
+        RenderStats {
+            faces_processed: self.faces_processed.load(Ordering::Relaxed),
+            faces_rendered: self.faces_rendered.load(Ordering::Relaxed),
+            faces_culled_small: self.faces_culled_small.load(Ordering::Relaxed),
+            faces_empty: self.faces_empty.load(Ordering::Relaxed),
+            instances: self.instances.load(Ordering::Relaxed),
+            instances_culled: self.instances_culled.load(Ordering::Relaxed),
+            viewport_instances: self.viewport_instances.load(Ordering::Relaxed),
+            calls: self.calls.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FaceDebugInfo<'a> {
+    // vertex positions in framebuffer pixels, two for lines and three for triangles
+    pub screen_points: &'a [Point2<f32>],
+    pub scissor: Option<&'a Scissor>,
+
+    // true if the primitive was rejected before rasterization
+    pub culled: bool,
+}
+
+pub type FaceObserver = Box<dyn FnMut(&FaceDebugInfo) + Send>;
+
+// a stack deeper than this almost certainly means a push is missing its pop
+pub const DEFAULT_MAX_RENDER_TARGET_DEPTH: usize = 16;
+
+pub const DEFAULT_MAX_POINT_SIZE: f32 = 64.0;
+
+// pixels each parallel task writes at the least when the scanline granularity is automatic. rows
+// are contiguous, so tasks much smaller than a few cache lines end up sharing them with the tasks
+// next to them
+pub const MIN_PIXELS_PER_TASK: usize = 64;
+
+// a finer grid than this can't be represented in an f32 for framebuffers much over 256 pixels
+// wide anyway
+pub const MAX_SUBPIXEL_BITS: u8 = 16;
+
+pub struct Rasterizer {
+    stats: AtomicRenderStats,
+    render_targets: LinkedList<Arc<Mutex<Framebuffer>>>,
+    max_render_target_depth: usize,
+
+    // each entry is already intersected with the one below it, so only the top needs checking
+    scissors: LinkedList<Scissor>,
+
+    // None uses the global rayon pool. shared with async renders
+    thread_pool: Option<Arc<ThreadPool>>,
+
+    // minimum number of scanlines handed to a thread at once. None picks enough rows to cover
+    // MIN_PIXELS_PER_TASK, which only matters for narrow framebuffers
+    scanline_granularity: Option<usize>,
+
+    face_observer: Option<FaceObserver>,
+
+    // larger point sizes in calls using Topology::PointList are clamped to this. lines are always
+    // one pixel wide
+    max_point_size: f32,
+
+    // scan index buffers against IndexedRenderCall::vertex_count before rendering
+    validate_indices: bool,
+
+    origin: Origin,
+
+    // scale and offset mapping NDC of the whole output onto the tile being rendered by
+    // render_tiled_to
+    tile_transform: Option<(Vector2<f32>, Vector2<f32>)>,
+
+    // fractional bits of the subpixel grid triangle vertices are snapped to before edge tests
+    subpixel_bits: Option<u8>,
+}
+
+impl Rasterizer {
+    pub fn new() -> Rasterizer {
+        Rasterizer {
+            stats: AtomicRenderStats::default(),
+            render_targets: LinkedList::new(),
+            max_render_target_depth: DEFAULT_MAX_RENDER_TARGET_DEPTH,
+            scissors: LinkedList::new(),
+            thread_pool: None,
+            scanline_granularity: None,
+            face_observer: None,
+            max_point_size: DEFAULT_MAX_POINT_SIZE,
+            validate_indices: true,
+            origin: Origin::default(),
+            tile_transform: None,
+            subpixel_bits: None,
+        }
+    }
+
+    pub fn with_thread_pool(pool: ThreadPool) -> Rasterizer {
+        Rasterizer {
+            thread_pool: Some(Arc::new(pool)),
+            ..Self::new()
+        }
+    }
+
+    pub fn set_scanline_granularity(&mut self, rows: Option<usize>) {
+        self.scanline_granularity = rows;
+    }
+
+    fn rows_per_task(&self, width: usize) -> usize {
+        match self.scanline_granularity {
+            Some(rows) => rows.max(1),
+            None => MIN_PIXELS_PER_TASK.div_ceil(width.max(1)),
+        }
+    }
+
+    pub fn new_frame(&mut self) -> Result<(), RasterizerError> {
+        if !self.render_targets.is_empty() {
+            Err(RasterizerError::RenderTargetUnfinished)
+        } else {
+            self.stats = AtomicRenderStats::default();
+            Ok(())
+        }
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats.snapshot()
+    }
+
+    // called for every primitive in every viewport once its scissor has been computed. pass None
+    // to remove the observer
+    pub fn set_face_observer(&mut self, observer: Option<FaceObserver>) {
+        self.face_observer = observer;
+    }
+
+    pub fn set_max_point_size(&mut self, size: f32) {
+        self.max_point_size = size;
+    }
+
+    // trusting indices skips a pass over every index buffer, but an out of range index will then
+    // panic in the vertex stage instead of returning an error. validating only covers calls that
+    // set vertex_count, calls without one are never checked
+    pub fn set_validate_indices(&mut self, validate: bool) {
+        self.validate_indices = validate;
+    }
+
+    fn validate_indices<T: Shader>(
+        &self,
+        call: &IndexedRenderCall<T>,
+    ) -> Result<(), RasterizerError> {
+        let Some(vertex_count) = call.vertex_count.filter(|_| self.validate_indices) else {
+            return Ok(());
+        };
+
+        let out_of_range = call
+            .indices
+            .iter()
+            .filter(|index| Some(**index) != call.primitive_restart)
+            .map(|index| *index as usize)
+            .find(|index| *index >= vertex_count);
+
+        match out_of_range {
+            Some(index) => Err(RasterizerError::IndexOutOfRange {
+                index,
+                max: vertex_count,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn set_origin(&mut self, origin: Origin) {
+        self.origin = origin;
+    }
+
+    // snaps the screen space vertices of triangles to a grid of 1 / 2^bits of a pixel, like
+    // hardware rasterizers do with fixed point coordinates, so tiny differences in vertex
+    // positions can't flip pixels on an edge. capped at MAX_SUBPIXEL_BITS. None leaves vertices
+    // as they are
+    pub fn set_subpixel_bits(&mut self, bits: Option<u8>) {
+        self.subpixel_bits = bits.map(|bits| bits.min(MAX_SUBPIXEL_BITS));
+    }
+
+    // mirrors vertex positions for a bottom left origin. the faces mirror with them, so winding is
+    // swapped to keep culling the same faces as with a top left origin
+    fn origin_call<'a, T: Shader>(
+        &self,
+        call: &IndexedRenderCall<'a, T>,
+    ) -> Option<IndexedRenderCall<'a, T>> {
+        match self.origin {
+            Origin::TopLeft => None,
+            Origin::BottomLeft => Some(IndexedRenderCall {
+                scissor: call.scissor.clone(),
+                render_area: call.render_area.clone(),
+                flip_winding: !call.flip_winding,
+                ..*call
+            }),
+        }
+    }
+
+    fn transform_positions<W>(&self, vertex_output: &mut [VertexOutput<W>]) {
+        if self.origin == Origin::BottomLeft {
+            for output in vertex_output.iter_mut() {
+                output.position.y = -output.position.y;
+            }
+        }
+
+        if let Some((scale, offset)) = &self.tile_transform {
+            for output in vertex_output {
+                output.position.x = output.position.x * scale.x + offset.x;
+                output.position.y = output.position.y * scale.y + offset.y;
+            }
+        }
+    }
+
+    fn validate_depth_source<T: Shader>(
+        call: &IndexedRenderCall<T>,
+        framebuffer: &Framebuffer,
+    ) -> Result<(), RasterizerError> {
+        match call.depth_source {
+            Some(source) if source.size() != framebuffer.size() => {
+                Err(RasterizerError::DepthSourceSizeMismatch)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_blending<T: Shader>(
+        call: &IndexedRenderCall<T>,
+        framebuffer: &Framebuffer,
+    ) -> Result<(), RasterizerError> {
+        let expected = framebuffer.color_attachments().len();
+
+        match &call.pipeline.blending {
+            Some(blending) if blending.len() != expected => {
+                Err(RasterizerError::BlendAttachmentCountMismatch {
+                    expected,
+                    actual: blending.len(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_index_count<T: Shader>(call: &IndexedRenderCall<T>) -> Result<(), RasterizerError> {
+        let trailing = call
+            .pipeline
+            .topology
+            .trailing_indices(call.indices, call.primitive_restart);
+
+        match trailing {
+            0 => Ok(()),
+            count => Err(RasterizerError::IncompletePrimitive(count)),
+        }
+    }
+
+    fn validate_primitive_size<T: Shader>(
+        &self,
+        call: &IndexedRenderCall<T>,
+    ) -> Result<(), RasterizerError> {
+        // sizes past the maximum are clamped to it rather than rejected
+        let size = call.pipeline.point_size;
+        match (call.pipeline.topology, size >= 0.0) {
+            (Topology::PointList, false) => Err(RasterizerError::InvalidPrimitiveSize),
+            _ => Ok(()),
+        }
+    }
+
+    fn point_size<T: Shader>(&self, call: &IndexedRenderCall<T>) -> f32 {
+        call.pipeline.point_size.min(self.max_point_size)
+    }
+
+    pub fn push_render_target(
+        &mut self,
+        target: Arc<Mutex<Framebuffer>>,
+    ) -> Result<(), RasterizerError> {
+        if self.render_targets.len() >= self.max_render_target_depth {
+            return Err(RasterizerError::RenderTargetStackOverflow);
+        }
+
+        self.render_targets.push_back(target);
+        Ok(())
+    }
+
+    pub fn render_target_depth(&self) -> usize {
+        self.render_targets.len()
+    }
+
+    // targets already on the stack are kept even if there are more than the new limit
+    pub fn set_max_render_target_depth(&mut self, depth: usize) {
+        self.max_render_target_depth = depth;
+    }
+
+    pub fn pop_render_target(&mut self) -> Result<(), RasterizerError> {
+        match self.render_targets.pop_back() {
+            Some(_) => Ok(()),
+            None => Err(RasterizerError::NoRenderTarget),
+        }
+    }
+
+    // drops every pushed render target, e.g. after an error left the stack in an unknown state
+    pub fn clear_render_targets(&mut self) {
+        self.render_targets.clear();
+    }
+
+    // bounds every following clear and draw until popped, on top of any per-call scissor
+    pub fn push_scissor(&mut self, scissor: Scissor) {
+        let scissor = match self.scissors.back() {
+            Some(top) => top.intersect_with(&scissor).unwrap_or(Scissor {
+                width: 0,
+                height: 0,
+                ..scissor
+            }),
+            None => scissor,
+        };
+
+        self.scissors.push_back(scissor);
+    }
+
+    pub fn pop_scissor(&mut self) -> Result<(), RasterizerError> {
+        match self.scissors.pop_back() {
+            Some(_) => Ok(()),
+            None => Err(RasterizerError::NoScissor),
+        }
+    }
+
+    pub fn current_scissor(&self) -> Option<&Scissor> {
+        self.scissors.back()
+    }
+
+    // clears the current render target, limited to the current scissor if there is one
+    pub fn clear(&mut self, value: &ClearValue) -> Result<(), RasterizerError> {
+        if !value.is_valid() {
+            return Err(RasterizerError::InvalidClearDepth);
+        }
+
+        let top = self.current_render_target()?;
+        let mut framebuffer = top.lock().unwrap();
+        if framebuffer.depth_attachment().is_some()
+            && !framebuffer.depth_format().contains(value.depth)
+        {
+            return Err(RasterizerError::ClearDepthOutOfRange);
+        }
+
+        // already validated, so neither can fail
+        match self.scissors.back() {
+            Some(scissor) => framebuffer.clear_region(value, scissor).unwrap(),
+            None => framebuffer.clear(value).unwrap(),
+        }
+
+        Ok(())
+    }
+
+    pub fn current_render_target(&mut self) -> Result<Arc<Mutex<Framebuffer>>, RasterizerError> {
+        match self.render_targets.back() {
+            Some(top) => Ok(top.clone()),
+            None => Err(RasterizerError::NoRenderTarget),
+        }
+    }
 
-        let vertex_output = array::from_fn(|i| {
-            call.pipeline.shader.vertex_stage(&VertexContext {
-                vertex_id: call.indices[index_offset + i] as usize,
-                instance_id: instance_id,
-                data: call.data,
+    fn render_face<T: Shader + Sync>(
+        &mut self,
+        instance_id: usize,
+        vertex_output: &[VertexOutput<T::Working>],
+        viewports: &[Viewport],
+        call: &IndexedRenderCall<T>,
+        fb_size: (usize, usize),
+        scanlines: &mut [MutableScanline],
+    ) -> Option<Scissor> {
+        let point_size = self.point_size(call);
+
+        let mut touched = None;
+        for viewport in viewports {
+            // point quads are sized in pixels, so they are expanded separately for every viewport
+            if vertex_output.len() == VERTICES_PER_POINT {
+                let mut quad = Vec::with_capacity(POINT_QUAD.len());
+                expand_point(call, &vertex_output[0], point_size, viewport, &mut quad);
+
+                for triangle in quad.chunks_exact(VERTICES_PER_FACE) {
+                    let scissor = self.render_face_viewport(
+                        instance_id,
+                        triangle,
+                        viewport,
+                        call,
+                        fb_size,
+                        scanlines,
+                    );
+                    touched = union_scissors(touched, scissor);
+                }
+            } else {
+                let scissor = self.render_face_viewport(
+                    instance_id,
+                    vertex_output,
+                    viewport,
+                    call,
+                    fb_size,
+                    scanlines,
+                );
+                touched = union_scissors(touched, scissor);
+            }
+        }
+
+        touched
+    }
+
+    // returns the region of the framebuffer a primitive may cover within a viewport, or None if
+    // the primitive can be skipped entirely
+    fn face_scissor<T: Shader>(
+        &mut self,
+        vertex_output: &[VertexOutput<T::Working>],
+        viewport: &Viewport,
+        call: &IndexedRenderCall<T>,
+        fb_width: usize,
+        fb_height: usize,
+    ) -> Option<Scissor> {
+        // an empty viewport covers no pixels and can't be mapped to NDC
+        if viewport.width == 0 || viewport.height == 0 {
+            return None;
+        }
+
+        // bounds have to come from the same snapped positions the edge tests use
+        let subpixel_bits = self
+            .subpixel_bits
+            .filter(|_| vertex_output.len() == VERTICES_PER_FACE);
+
+        let uv: Vec<_> = vertex_output
+            .iter()
+            .map(|output| {
+                let position = match subpixel_bits {
+                    Some(bits) => viewport.snap_ndc(output.position.xy(), bits),
+                    None => output.position.xy(),
+                };
+
+                position.map(|x| (x + 1.0) / 2.0)
             })
-        });
+            .collect();
+
+        let culled_small = vertex_output.len() == VERTICES_PER_FACE
+            && call.pipeline.min_triangle_area.is_some_and(|min_area| {
+                let pixels: [_; VERTICES_PER_FACE] = array::from_fn(|i| {
+                    Point2::new(
+                        uv[i].x * viewport.width as f32,
+                        uv[i].y * viewport.height as f32,
+                    )
+                });
 
-        let uv = vertex_output
-            .each_ref()
-            .map(|output| output.position.xy().map(|x| (x + 1.0) / 2.0));
+                let winding = call.winding_order();
+                signed_triangle_area(pixels.each_ref(), winding).abs() < min_area
+            });
 
-        let generated_scissor = gen_scissor(&uv, fb_width, fb_height);
-        let final_scissor = match &call.scissor {
-            Some(user_scissor) => generated_scissor.intersect_with(user_scissor),
-            None => Some(generated_scissor), // move
+        let final_scissor = if culled_small {
+            add_stat(&self.stats.faces_culled_small, 1);
+            None
+        } else {
+            let generated_scissor = match &call.scissor {
+                Some(user_scissor) if call.use_user_scissor_only => user_scissor.clone(),
+                _ => self.gen_face_scissor(&uv, viewport, call),
+            };
+
+            let bounds = viewport
+                .to_scissor()
+                .intersect_with(&Viewport::full(fb_width, fb_height).to_scissor())
+                .and_then(|bounds| match &call.render_area {
+                    Some(render_area) => bounds.intersect_with(render_area),
+                    None => Some(bounds), // move
+                });
+
+            bounds
+                .and_then(|bounds| generated_scissor.intersect_with(&bounds))
+                .and_then(|scissor| match &call.scissor {
+                    Some(user_scissor) => scissor.intersect_with(user_scissor),
+                    None => Some(scissor), // move
+                })
+                .and_then(|scissor| match self.scissors.back() {
+                    Some(stack_scissor) => scissor.intersect_with(stack_scissor),
+                    None => Some(scissor), // move
+                })
         };
 
-        if let Some(scissor) = final_scissor {
+        if let Some(observer) = &mut self.face_observer {
+            let screen_points: Vec<_> = uv
+                .iter()
+                .map(|p| {
+                    Point2::new(
+                        viewport.x as f32 + p.x * viewport.width as f32,
+                        viewport.y as f32 + p.y * viewport.height as f32,
+                    )
+                })
+                .collect();
+
+            observer(&FaceDebugInfo {
+                screen_points: &screen_points,
+                scissor: final_scissor.as_ref(),
+                culled: final_scissor.is_none(),
+            });
+        }
+
+        final_scissor
+    }
+
+    // the generated scissor is relative to the viewport, so move it into framebuffer space. the
+    // caller makes sure neither the viewport nor the face leave the framebuffer
+    fn gen_face_scissor<T: Shader>(
+        &self,
+        uv: &[Point2<f32>],
+        viewport: &Viewport,
+        call: &IndexedRenderCall<T>,
+    ) -> Scissor {
+        let mut generated_scissor = match uv.len() {
+            VERTICES_PER_LINE => {
+                // antialiased lines also shade the pixels next to the ones they pass through
+                let margin = match call.pipeline.line_rasterization {
+                    LineRasterization::Aliased => 0,
+                    LineRasterization::Antialiased => 1,
+                };
+
+                gen_line_scissor(uv, viewport.width, viewport.height, margin)
+            }
+            _ => gen_scissor(uv, viewport.width, viewport.height),
+        };
+
+        generated_scissor.x += viewport.x;
+        generated_scissor.y += viewport.y;
+
+        if generated_scissor.width == 0 || generated_scissor.height == 0 {
+            add_stat(&self.stats.faces_empty, 1);
+        }
+
+        generated_scissor
+    }
+
+    // scanlines are every row of the framebuffer, built once per call and shared by all of its
+    // faces instead of being collected again for each one
+    fn render_face_viewport<T: Shader + Sync>(
+        &mut self,
+        instance_id: usize,
+        vertex_output: &[VertexOutput<T::Working>],
+        viewport: &Viewport,
+        call: &IndexedRenderCall<T>,
+        fb_size: (usize, usize),
+        scanlines: &mut [MutableScanline],
+    ) -> Option<Scissor> {
+        let (fb_width, fb_height) = fb_size;
+        let final_scissor = self.face_scissor(vertex_output, viewport, call, fb_width, fb_height);
+
+        if let Some(scissor) = &final_scissor {
             let fc = FaceContext {
                 instance_id,
                 call,
-                vertex_output: &vertex_output,
-                fb_width,
-                fb_height,
+                vertex_output,
+                viewport,
+                subpixel_bits: self.subpixel_bits,
             };
 
-            framebuffer
-                .scanlines(scissor.y, scissor.height)
-                .par_iter_mut()
-                .for_each(|scanline| {
-                    for delta_x in 0..scissor.width {
-                        process_pixel(scissor.x + delta_x, &fc, scanline);
-                    }
-                });
+            let rows_per_task = self.rows_per_task(fb_width);
+            let scanlines = &mut scanlines[scissor.y..scissor.y + scissor.height];
+
+            // lines touch very few pixels per row, so they arent worth splitting up
+            if vertex_output.len() == VERTICES_PER_LINE {
+                process_line(&fc, scissor, scanlines);
+            } else {
+                // every scanline owns a disjoint row of each attachment, so the parallel section
+                // never races and the output is identical no matter how rayon schedules the rows
+                let mut render = || {
+                    scanlines
+                        .par_iter_mut()
+                        .with_min_len(rows_per_task)
+                        .for_each(|scanline| {
+                            process_span(scissor.x, scissor.x + scissor.width, &fc, scanline);
+                        })
+                };
+
+                match &self.thread_pool {
+                    Some(pool) => pool.install(render),
+                    None => render(),
+                }
+            }
 
-            self.stats.faces_rendered += 1;
+            add_stat(&self.stats.faces_rendered, 1);
         }
+
+        final_scissor
     }
 
+    // zero instances or an empty index buffer draw nothing but still count as a call. list
+    // topologies must be given whole primitives, any trailing indices are an error
     pub fn render_indexed<T: Shader + Sync>(
         &mut self,
         call: &IndexedRenderCall<T>,
     ) -> Result<(), RasterizerError> {
-        let face_count = call.indices.len() / VERTICES_PER_FACE;
+        self.validate_primitive_size(call)?;
+        Self::validate_index_count(call)?;
+        self.validate_indices(call)?;
 
-        // todo: do we care about unused indices?
+        let top = self.current_render_target()?;
+        let mut framebuffer = top.lock().unwrap();
+        self.draw_indexed(call, &mut framebuffer)?;
+        Ok(())
+    }
+
+    // same as render_indexed, but returns the union of the scissors of every face that was
+    // rendered, e.g. to only present the part of the framebuffer that could have changed. every
+    // pixel written is inside of it, but not every pixel inside of it was written. an empty
+    // scissor at the origin if nothing was rendered
+    pub fn render_indexed_touched<T: Shader + Sync>(
+        &mut self,
+        call: &IndexedRenderCall<T>,
+    ) -> Result<Scissor, RasterizerError> {
+        self.validate_primitive_size(call)?;
+        Self::validate_index_count(call)?;
+        self.validate_indices(call)?;
 
         let top = self.current_render_target()?;
         let mut framebuffer = top.lock().unwrap();
+        let touched = self.draw_indexed(call, &mut framebuffer)?;
+
+        Ok(touched.unwrap_or(Scissor {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        }))
+    }
 
+    // same as render_indexed, but renders straight into a borrowed framebuffer instead of the
+    // current render target, so single threaded renders don't need an Arc<Mutex>. the scissor
+    // stack, origin and stats still apply
+    pub fn render_indexed_into<T: Shader + Sync>(
+        &mut self,
+        call: &IndexedRenderCall<T>,
+        framebuffer: &mut Framebuffer,
+    ) -> Result<(), RasterizerError> {
+        self.validate_primitive_size(call)?;
+        Self::validate_index_count(call)?;
+        self.validate_indices(call)?;
+
+        self.draw_indexed(call, framebuffer)?;
+        Ok(())
+    }
+
+    // the call must already be validated, apart from the checks against the framebuffer. returns
+    // the union of the scissors of every rendered face
+    fn draw_indexed<T: Shader + Sync>(
+        &mut self,
+        call: &IndexedRenderCall<T>,
+        framebuffer: &mut Framebuffer,
+    ) -> Result<Option<Scissor>, RasterizerError> {
+        let origin_call = self.origin_call(call);
+        let call = origin_call.as_ref().unwrap_or(call);
+
+        let primitives = call
+            .pipeline
+            .topology
+            .assemble(call.indices, call.primitive_restart);
+
+        // todo: do we care about unused indices?
+
+        Self::validate_blending(call, framebuffer)?;
+        Self::validate_depth_source(call, framebuffer)?;
+
+        let (fb_width, fb_height) = framebuffer.size();
+        let default_viewport = [default_viewport(call, fb_width, fb_height)];
+        let viewports = match call.viewports.len() {
+            0 => &default_viewport[..],
+            _ => call.viewports,
+        };
+
+        // faces are rendered one at a time in submission order, so overlapping faces always blend
+        // and depth test against each other deterministically
+        let fb_size = (fb_width, fb_height);
+        let mut scanlines = framebuffer.scanlines(0, fb_height);
+        let mut vertex_output = Vec::new();
+        let mut touched = None;
         for i in 0..call.instance_count {
-            for j in 0..face_count {
-                self.render_face(call.first_instance + i, j, call, &mut framebuffer);
-                self.stats.faces_processed += 1;
+            let instance_id = call.first_instance + i;
+            if instance_culled(call, instance_id) {
+                add_stat(&self.stats.instances_culled, 1);
+                continue;
             }
 
-            self.stats.instances += 1;
+            let instance = call
+                .pipeline
+                .shader
+                .prepare_instance(call.data, instance_id);
+            for primitive in &primitives {
+                vertex_output.clear();
+                shade_primitive(instance_id, &instance, primitive, call, &mut vertex_output);
+                self.transform_positions(&mut vertex_output);
+
+                let vertex_count = vertex_output.len();
+                clip_and_tessellate(call, &mut vertex_output, 0);
+
+                for face in vertex_output.chunks_exact(vertex_count) {
+                    let scissor = self.render_face(
+                        instance_id,
+                        face,
+                        viewports,
+                        call,
+                        fb_size,
+                        &mut scanlines,
+                    );
+                    touched = union_scissors(touched, scissor);
+                }
+
+                add_stat(&self.stats.faces_processed, 1);
+            }
+
+            add_stat(&self.stats.instances, 1);
+            add_stat(&self.stats.viewport_instances, viewports.len());
+        }
+
+        add_stat(&self.stats.calls, 1);
+        Ok(touched)
+    }
+
+    // renders to the current render target on a separate thread. the framebuffer stays locked
+    // until the render finishes. the render runs on its own rasterizer with this one's settings and
+    // top scissor, but the face observer can't be shared with it, so it isn't called
+    pub fn render_indexed_async<T>(
+        &mut self,
+        call: AsyncRenderCall<T>,
+    ) -> Result<RenderHandle, RasterizerError>
+    where
+        T: Shader + Send + Sync + 'static,
+        T::Uniform: Send,
+    {
+        let target = self.current_render_target()?;
+        let thread_pool = self.thread_pool.clone();
+        let scanline_granularity = self.scanline_granularity;
+        let max_point_size = self.max_point_size;
+        let validate_indices = self.validate_indices;
+        let origin = self.origin;
+        let subpixel_bits = self.subpixel_bits;
+        let max_render_target_depth = self.max_render_target_depth;
+        let scissors = LinkedList::from_iter(self.scissors.back().cloned());
+
+        let thread = thread::spawn(move || {
+            let mut worker = Rasterizer {
+                scissors,
+                thread_pool,
+                scanline_granularity,
+                max_point_size,
+                validate_indices,
+                origin,
+                subpixel_bits,
+                max_render_target_depth,
+                ..Self::new()
+            };
+
+            worker.push_render_target(target)?;
+            worker.render_indexed(&IndexedRenderCall {
+                pipeline: &call.pipeline,
+                vertex_offset: call.vertex_offset,
+                first_instance: call.first_instance,
+                instance_count: call.instance_count,
+                scissor: call.scissor,
+                use_user_scissor_only: call.use_user_scissor_only,
+                render_area: call.render_area,
+                viewports: &call.viewports,
+                indices: &call.indices,
+                vertex_count: call.vertex_count,
+                primitive_restart: call.primitive_restart,
+                flip_winding: call.flip_winding,
+                clip_planes: &call.clip_planes,
+                depth_source: call.depth_source.as_deref(),
+                data: &call.data,
+            })?;
+
+            Ok(worker.stats())
+        });
+
+        Ok(RenderHandle { thread })
+    }
+
+    // renders faces in parallel by splitting the framebuffer into horizontal tiles of
+    // `tile_height` rows. each tile is owned by a single thread which walks every face overlapping
+    // it in submission order, so blending and depth testing between overlapping faces produce the
+    // exact same output as render_indexed. faces are never reordered within a tile, even ones
+    // that don't overlap, since whether they overlap isn't known until they're rasterized
+    pub fn render_indexed_tiled<T: Shader + Sync>(
+        &mut self,
+        call: &IndexedRenderCall<T>,
+        tile_height: usize,
+    ) -> Result<(), RasterizerError> {
+        self.validate_primitive_size(call)?;
+        Self::validate_index_count(call)?;
+        self.validate_indices(call)?;
+
+        let origin_call = self.origin_call(call);
+        let call = origin_call.as_ref().unwrap_or(call);
+
+        let primitives = call
+            .pipeline
+            .topology
+            .assemble(call.indices, call.primitive_restart);
+
+        // the tiles are handed to other threads, which can't hold on to the guard itself
+        let top = self.current_render_target()?;
+        let mut guard = top.lock().unwrap();
+        let framebuffer = &mut *guard;
+        Self::validate_blending(call, framebuffer)?;
+        Self::validate_depth_source(call, framebuffer)?;
+
+        let (fb_width, fb_height) = framebuffer.size();
+        let default_viewport = [default_viewport(call, fb_width, fb_height)];
+        let viewports = match call.viewports.len() {
+            0 => &default_viewport[..],
+            _ => call.viewports,
+        };
+
+        let point_size = self.point_size(call);
+
+        // shade every face up front and bin the visible ones in submission order
+        let mut vertex_outputs = Vec::new();
+        let mut binned_faces = Vec::new();
+
+        for i in 0..call.instance_count {
+            let instance_id = call.first_instance + i;
+            if instance_culled(call, instance_id) {
+                add_stat(&self.stats.instances_culled, 1);
+                continue;
+            }
+
+            let instance = call
+                .pipeline
+                .shader
+                .prepare_instance(call.data, instance_id);
+            for primitive in &primitives {
+                let vertex_offset = vertex_outputs.len();
+                shade_primitive(instance_id, &instance, primitive, call, &mut vertex_outputs);
+                self.transform_positions(&mut vertex_outputs[vertex_offset..]);
+
+                let vertex_count = vertex_outputs.len() - vertex_offset;
+                clip_and_tessellate(call, &mut vertex_outputs, vertex_offset);
+
+                // clipping and tessellation may have split the primitive up, or clipping removed
+                // it entirely
+                let clipped_end = vertex_outputs.len();
+                for primitive_offset in (vertex_offset..clipped_end).step_by(vertex_count) {
+                    for viewport in viewports {
+                        // faces to bin as (offset, count) ranges of the vertex output buffer
+                        let faces: Vec<_> = if vertex_count == VERTICES_PER_POINT {
+                            let mut quad = Vec::with_capacity(POINT_QUAD.len());
+                            expand_point(
+                                call,
+                                &vertex_outputs[primitive_offset],
+                                point_size,
+                                viewport,
+                                &mut quad,
+                            );
+
+                            let quad_offset = vertex_outputs.len();
+                            vertex_outputs.extend(quad);
+
+                            (quad_offset..vertex_outputs.len())
+                                .step_by(VERTICES_PER_FACE)
+                                .map(|offset| (offset, VERTICES_PER_FACE))
+                                .collect()
+                        } else {
+                            vec![(primitive_offset, vertex_count)]
+                        };
+
+                        for (offset, count) in faces {
+                            let vertex_output = &vertex_outputs[offset..offset + count];
+                            let scissor = self.face_scissor(
+                                vertex_output,
+                                viewport,
+                                call,
+                                fb_width,
+                                fb_height,
+                            );
+
+                            if let Some(scissor) = scissor {
+                                binned_faces.push(BinnedFace {
+                                    instance_id,
+                                    submission: binned_faces.len(),
+                                    vertex_offset: offset,
+                                    vertex_count: count,
+                                    viewport,
+                                    scissor,
+                                });
+
+                                add_stat(&self.stats.faces_rendered, 1);
+                            }
+                        }
+                    }
+                }
+
+                add_stat(&self.stats.faces_processed, 1);
+            }
+
+            add_stat(&self.stats.instances, 1);
+            add_stat(&self.stats.viewport_instances, viewports.len());
+        }
+
+        let subpixel_bits = self.subpixel_bits;
+        if fb_height > 0 && !binned_faces.is_empty() {
+            let mut render = || {
+                framebuffer
+                    .scanlines(0, fb_height)
+                    .par_chunks_mut(tile_height.max(1))
+                    .for_each(|tile| {
+                        let tile_y = tile[0].y;
+
+                        // faces were binned in submission order and every tile has to walk
+                        // them in that order. this only guards against the loop being changed
+                        // to skip ahead or reorder, e.g. by sorting faces per tile
+                        let mut last_submission = None;
+                        for face in &binned_faces {
+                            debug_assert!(
+                                last_submission.is_none_or(|last| last < face.submission),
+                                "Faces must be processed in submission order within a tile!"
+                            );
+                            last_submission = Some(face.submission);
+
+                            let y0 = face.scissor.y.max(tile_y);
+                            let y1 =
+                                (face.scissor.y + face.scissor.height).min(tile_y + tile.len());
+
+                            if y1 <= y0 {
+                                continue;
+                            }
+
+                            let fc = FaceContext {
+                                instance_id: face.instance_id,
+                                call,
+                                vertex_output: &vertex_outputs
+                                    [face.vertex_offset..face.vertex_offset + face.vertex_count],
+                                viewport: face.viewport,
+                                subpixel_bits,
+                            };
+
+                            let rows = &mut tile[(y0 - tile_y)..(y1 - tile_y)];
+                            if face.vertex_count == VERTICES_PER_LINE {
+                                process_line(&fc, &face.scissor, rows);
+                                continue;
+                            }
+
+                            let x1 = face.scissor.x + face.scissor.width;
+                            for scanline in rows {
+                                process_span(face.scissor.x, x1, &fc, scanline);
+                            }
+                        }
+                    })
+            };
+
+            match &self.thread_pool {
+                Some(pool) => pool.install(render),
+                None => render(),
+            }
+        }
+
+        add_stat(&self.stats.calls, 1);
+        Ok(())
+    }
+
+    // renders an output of total_size one tile of at most tile_size at a time, so the whole output
+    // never has to be in memory. every tile is cleared, drawn into a framebuffer of its own shaped
+    // like the current render target, and passed to sink along with the region of the output it
+    // covers. the call's scissor and depth source cover the whole output, while its render area
+    // and viewports are ignored
+    pub fn render_tiled_to<T, F>(
+        &mut self,
+        total_size: (usize, usize),
+        tile_size: (usize, usize),
+        call: &IndexedRenderCall<T>,
+        clear: &ClearValue,
+        sink: F,
+    ) -> Result<(), RasterizerError>
+    where
+        T: Shader + Sync,
+        F: FnMut(Scissor, &Image<u32>),
+    {
+        if !clear.is_valid() {
+            return Err(RasterizerError::InvalidClearDepth);
+        } else if call
+            .depth_source
+            .is_some_and(|source| source.size() != total_size)
+        {
+            return Err(RasterizerError::DepthSourceSizeMismatch);
+        } else if total_size.0 == 0 || total_size.1 == 0 {
+            return Ok(());
+        }
+
+        let target = Arc::new(Mutex::new(self.tile_framebuffer()));
+        self.push_render_target(target.clone())?;
+
+        // the scissor stack is in the coordinates of the caller's render target
+        let scissors = mem::take(&mut self.scissors);
+        let result = self.render_tiles(total_size, tile_size, call, clear, &target, sink);
+
+        self.scissors = scissors;
+        self.tile_transform = None;
+        self.pop_render_target()?;
+
+        result
+    }
+
+    // the tiles are rendered with the same attachments and formats as the current render target,
+    // so the pipeline sees what it would when drawing into it directly. sink only gets the first
+    // color attachment, so there's always at least one
+    fn tile_framebuffer(&self) -> Framebuffer {
+        let info = match self.render_targets.back() {
+            Some(top) => top.lock().unwrap().info(),
+            None => return Framebuffer::new(1, 1, 1, true),
+        };
+
+        let desc = FramebufferDesc {
+            color_attachments: info.color_attachments.max(1),
+            depth: info.has_depth,
+            depth_format: info.depth_format,
+            ..FramebufferDesc::new(1, 1)
+        };
+
+        let mut framebuffer = Framebuffer::from_desc(&desc).unwrap();
+        framebuffer.set_color_format(info.color_format);
+        framebuffer
+    }
+
+    fn render_tiles<T, F>(
+        &mut self,
+        total_size: (usize, usize),
+        tile_size: (usize, usize),
+        call: &IndexedRenderCall<T>,
+        clear: &ClearValue,
+        target: &Arc<Mutex<Framebuffer>>,
+        mut sink: F,
+    ) -> Result<(), RasterizerError>
+    where
+        T: Shader + Sync,
+        F: FnMut(Scissor, &Image<u32>),
+    {
+        let (total_width, total_height) = total_size;
+        for y in (0..total_height).step_by(tile_size.1.max(1)) {
+            for x in (0..total_width).step_by(tile_size.0.max(1)) {
+                let tile = Scissor {
+                    x,
+                    y,
+
+                    width: tile_size.0.max(1).min(total_width - x),
+                    height: tile_size.1.max(1).min(total_height - y),
+                };
+
+                {
+                    let mut framebuffer = target.lock().unwrap();
+                    if framebuffer.size() != (tile.width, tile.height) {
+                        framebuffer.resize(tile.width, tile.height, false);
+                    }
+
+                    framebuffer.clear(clear).unwrap();
+                }
+
+                // maps output NDC so that the tile's pixels land on the tile framebuffer
+                let (width, height) = (tile.width as f32, tile.height as f32);
+                self.tile_transform = Some((
+                    Vector2::new(total_width as f32 / width, total_height as f32 / height),
+                    Vector2::new(
+                        (total_width as f32 - 2.0 * x as f32) / width - 1.0,
+                        (total_height as f32 - 2.0 * y as f32) / height - 1.0,
+                    ),
+                ));
+
+                let scissor = call.scissor.as_ref().map(|scissor| {
+                    scissor
+                        .intersect_with(&tile)
+                        .and_then(|visible| visible.offset_by(-(x as isize), -(y as isize)))
+                });
+
+                // validated against total_size up front, so every tile fits
+                let depth_source = call.depth_source.map(|source| source.crop(&tile).unwrap());
+
+                // a scissor missing the tile entirely leaves it cleared
+                if !matches!(scissor, Some(None)) {
+                    self.render_indexed(&IndexedRenderCall {
+                        scissor: scissor.flatten(),
+                        render_area: None,
+                        viewports: &[],
+                        depth_source: depth_source.as_ref(),
+                        ..*call
+                    })?;
+                }
+
+                let framebuffer = target.lock().unwrap();
+                sink(tile, &framebuffer.color_attachments()[0]);
+            }
         }
 
-        self.stats.calls += 1;
         Ok(())
     }
 }
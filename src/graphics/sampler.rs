@@ -0,0 +1,183 @@
+use nalgebra::{Point2, Vector2};
+
+use super::blending::Blendable;
+use super::image::Image;
+use super::scissor::Scissor;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+impl WrapMode {
+    fn wrap(&self, texel: isize, size: usize) -> usize {
+        let size = size as isize;
+
+        let wrapped = match self {
+            WrapMode::Repeat => texel.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let period = texel.rem_euclid(size * 2);
+                match period < size {
+                    true => period,
+                    false => size * 2 - 1 - period,
+                }
+            }
+            WrapMode::ClampToEdge => texel.clamp(0, size - 1),
+        };
+
+        wrapped as usize
+    }
+}
+
+// meant to be stored in a shader and passed to the sample functions from its fragment stage
+#[derive(Debug, Clone)]
+pub struct SamplerState {
+    pub filter: Filter,
+    pub wrap_u: WrapMode,
+    pub wrap_v: WrapMode,
+
+    // added to the computed level of detail, positive values select smaller mips
+    pub lod_bias: f32,
+
+    // not implemented yet, sampling is always isotropic
+    pub anisotropy: f32,
+}
+
+impl Default for SamplerState {
+    fn default() -> SamplerState {
+        SamplerState {
+            filter: Filter::Linear,
+            wrap_u: WrapMode::Repeat,
+            wrap_v: WrapMode::Repeat,
+
+            lod_bias: 0.0,
+            anisotropy: 1.0,
+        }
+    }
+}
+
+impl SamplerState {
+    // level of detail for a texture of the given size, from the screen space derivatives of the
+    // uv coordinates
+    pub fn lod(&self, size: (usize, usize), duv_dx: Vector2<f32>, duv_dy: Vector2<f32>) -> f32 {
+        let scale = Vector2::new(size.0 as f32, size.1 as f32);
+        let texels_x = duv_dx.component_mul(&scale).norm();
+        let texels_y = duv_dy.component_mul(&scale).norm();
+
+        texels_x.max(texels_y).max(f32::MIN_POSITIVE).log2() + self.lod_bias
+    }
+
+    fn texel<'a, T>(&self, image: &'a Image<T>, x: isize, y: isize) -> &'a T {
+        let (width, height) = image.size();
+        let x = self.wrap_u.wrap(x, width);
+        let y = self.wrap_v.wrap(y, height);
+
+        image.at(x, y).unwrap()
+    }
+}
+
+// samples a single image, ignoring the lod bias. uv (0, 0) is the top left corner of the image
+pub fn sample_image<T: Blendable + Copy>(
+    image: &Image<T>,
+    sampler: &SamplerState,
+    uv: Point2<f32>,
+) -> T {
+    let (width, height) = image.size();
+    let x = uv.x * width as f32;
+    let y = uv.y * height as f32;
+
+    match sampler.filter {
+        Filter::Nearest => *sampler.texel(image, x.floor() as isize, y.floor() as isize),
+        Filter::Linear => {
+            // texel centers are at half coordinates
+            let x = x - 0.5;
+            let y = y - 0.5;
+
+            let x0 = x.floor();
+            let y0 = y.floor();
+
+            let tx = x - x0;
+            let ty = y - y0;
+
+            // the cast saturates for infinite uvs, so the neighbours have to as well
+            let (x0, y0) = (x0 as isize, y0 as isize);
+            let (x1, y1) = (x0.saturating_add(1), y0.saturating_add(1));
+            let texels = [
+                sampler.texel(image, x0, y0),
+                sampler.texel(image, x1, y0),
+                sampler.texel(image, x0, y1),
+                sampler.texel(image, x1, y1),
+            ];
+
+            let weights = [
+                (1.0 - tx) * (1.0 - ty),
+                tx * (1.0 - ty),
+                (1.0 - tx) * ty,
+                tx * ty,
+            ];
+
+            T::blend(&texels, &weights)
+        }
+    }
+}
+
+// picks the nearest mip level for the uv derivatives and samples it. levels[0] is the full size
+// image, with every following level half the size of the previous one
+pub fn sample_mip_chain<T: Blendable + Copy>(
+    levels: &[Image<T>],
+    sampler: &SamplerState,
+    uv: Point2<f32>,
+    duv_dx: Vector2<f32>,
+    duv_dy: Vector2<f32>,
+) -> T {
+    let lod = sampler.lod(levels[0].size(), duv_dx, duv_dy);
+    let level = (lod.round().max(0.0) as usize).min(levels.len() - 1);
+
+    sample_image(&levels[level], sampler, uv)
+}
+
+// builds a mip chain down to 1x1 by averaging 2x2 blocks of texels
+pub fn generate_mip_chain<T: Blendable + Copy + Default>(image: &Image<T>) -> Vec<Image<T>> {
+    let (width, height) = image.size();
+    let full = Scissor {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    };
+
+    let mut levels = vec![image.crop(&full).unwrap()];
+
+    loop {
+        let previous = levels.last().unwrap();
+        let (width, height) = previous.size();
+        if width <= 1 && height <= 1 {
+            break;
+        }
+
+        let mut level = Image::new((width / 2).max(1), (height / 2).max(1));
+        for (x, y) in level.coordinates() {
+            let texel = |dx: usize, dy: usize| {
+                let sx = (x * 2 + dx).min(width - 1);
+                let sy = (y * 2 + dy).min(height - 1);
+
+                previous.at(sx, sy).unwrap()
+            };
+
+            let block = [texel(0, 0), texel(1, 0), texel(0, 1), texel(1, 1)];
+            level.exchange(x, y, T::blend(&block, &[0.25; 4]));
+        }
+
+        levels.push(level);
+    }
+
+    levels
+}
@@ -21,6 +21,28 @@ impl Scissor {
         x >= self.x && x < x1 && y >= self.y && y < y1
     }
 
+    // e.g. for mapping a scissor onto a supersampled target
+    pub fn scaled(&self, factor: usize) -> Scissor {
+        Scissor {
+            x: self.x * factor,
+            y: self.y * factor,
+
+            width: self.width * factor,
+            height: self.height * factor,
+        }
+    }
+
+    // None if the scissor would start left of or above the origin
+    pub fn offset_by(&self, dx: isize, dy: isize) -> Option<Scissor> {
+        Some(Scissor {
+            x: self.x.checked_add_signed(dx)?,
+            y: self.y.checked_add_signed(dy)?,
+
+            width: self.width,
+            height: self.height,
+        })
+    }
+
     pub fn intersect_with(&self, other: &Scissor) -> Option<Scissor> {
         let x0 = self.x.max(other.x);
         let y0 = self.y.max(other.y);
@@ -40,4 +62,21 @@ impl Scissor {
             })
         }
     }
+
+    // smallest scissor containing both
+    pub fn union_with(&self, other: &Scissor) -> Scissor {
+        let x0 = self.x.min(other.x);
+        let y0 = self.y.min(other.y);
+
+        let x1 = (self.x + self.width).max(other.x + other.width);
+        let y1 = (self.y + self.height).max(other.y + other.height);
+
+        Scissor {
+            x: x0,
+            y: y0,
+
+            width: x1 - x0,
+            height: y1 - y0,
+        }
+    }
 }
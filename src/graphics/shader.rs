@@ -1,17 +1,48 @@
-use nalgebra::Point3;
+use std::array;
+
+use nalgebra::{Matrix4, Point2, Point3, Point4, Vector4};
 
 use super::blending::Blendable;
 
 pub struct VertexContext<'a, U> {
     pub vertex_id: usize,
     pub instance_id: usize,
+
+    // whatever Shader::prepare_instance returned for this instance
+    pub instance: &'a InstanceCache,
+
     pub data: &'a U,
 }
 
+// values computed once per instance by Shader::prepare_instance instead of once per vertex,
+// usually the model view projection matrix
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InstanceCache {
+    pub transform: Option<Matrix4<f32>>,
+}
+
 pub struct FragmentContext<'a, U, W> {
     pub instance_id: usize,
+
+    // x and y are in NDC while z is the recovered depth
+    #[deprecated(note = "use frag_coord instead")]
     pub position: Point3<f32>,
 
+    // gl_FragCoord style, with this crate's depth. x and y are the framebuffer coordinates of the
+    // pixel center, always counted from the top left like viewports and scissors, even with
+    // Origin::BottomLeft. z is the fragment depth mapped to [0, 1] by the viewport's depth range,
+    // see Viewport::min_depth. vertex z doubles as clip w, so w is 1 / clip w like in gl_FragCoord
+    pub frag_coord: Point4<f32>,
+
+    // color already in the first color attachment at this pixel, only read when the pipeline
+    // sets read_dst
+    pub dst_color: Option<u32>,
+
+    // whether the face is in the pipeline's winding order on screen, after flip_winding. only
+    // false for back faces of pipelines that don't cull them, lines and points are always front
+    // facing
+    pub front_facing: bool,
+
     pub data: &'a U,
     pub working: W,
 }
@@ -21,6 +52,37 @@ pub struct VertexOutput<W> {
     pub data: W,
 }
 
+// axis aligned box around everything an instance draws, and the matrix taking it to clip space.
+// usually the box is in model space and transform is the model view projection matrix
+pub struct InstanceBounds {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+    pub transform: Matrix4<f32>,
+}
+
+impl InstanceBounds {
+    // true if every corner is behind the camera or outside the same edge of the screen. a box
+    // can miss the screen without this catching it, so it only ever rejects conservatively
+    pub fn is_outside(&self) -> bool {
+        let corners: [Vector4<f32>; 8] = array::from_fn(|i| {
+            let corner = Point3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+
+            self.transform * corner.to_homogeneous()
+        });
+
+        let all = |outside: fn(&Vector4<f32>) -> bool| corners.iter().all(outside);
+        all(|clip| clip.w <= 0.0)
+            || all(|clip| clip.x < -clip.w)
+            || all(|clip| clip.x > clip.w)
+            || all(|clip| clip.y < -clip.w)
+            || all(|clip| clip.y > clip.w)
+    }
+}
+
 pub struct ProcessedVertexOutput<'a, W: ?Sized> {
     pub data: &'a W,
     pub weight: f32,
@@ -32,4 +94,52 @@ pub trait Shader {
 
     fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working>;
     fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32;
+
+    // what the rasterizer actually calls. override to output a secondary color for the Src1
+    // blend factors, in which case fragment_stage is never called
+    fn fragment_stage_dual(
+        &self,
+        context: &FragmentContext<Self::Uniform, Self::Working>,
+    ) -> (u32, Option<u32>) {
+        (self.fragment_stage(context), None)
+    }
+
+    // position tested against IndexedRenderCall::clip_planes, usually the world space position
+    // carried in the working data. vertices without one are never clipped
+    fn clip_position(&self, _working: &Self::Working) -> Option<Point3<f32>> {
+        None
+    }
+
+    // bounds of everything the instance draws. instances whose bounds are entirely off screen are
+    // skipped before any of their vertices are shaded
+    fn instance_bounds(
+        &self,
+        _instance_id: usize,
+        _data: &Self::Uniform,
+    ) -> Option<InstanceBounds> {
+        None
+    }
+
+    // called once per instance before any of its vertices are shaded, and not at all for culled
+    // instances. the result is passed to vertex_stage through VertexContext::instance
+    fn prepare_instance(&self, _data: &Self::Uniform, _instance_id: usize) -> InstanceCache {
+        InstanceCache::default()
+    }
+
+    // called for every vertex of a tessellated triangle, including its corners, with its
+    // barycentric coordinates within the original triangle. displacement goes here
+    fn tessellate_vertex(
+        &self,
+        output: VertexOutput<Self::Working>,
+        _barycentric: Point3<f32>,
+    ) -> VertexOutput<Self::Working> {
+        output
+    }
+
+    // called for every corner of a point expanded by Topology::PointList, with a copy of the
+    // point's vertex output. uv is the corner's position within the quad, from (0, 0) at the top
+    // left to (1, 1) at the bottom right
+    fn point_corner(&self, working: Self::Working, _uv: Point2<f32>) -> Self::Working {
+        working
+    }
 }
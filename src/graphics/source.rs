@@ -0,0 +1,182 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+
+use super::blending::Blendable;
+use super::shader::{FragmentContext, Shader, VertexContext, VertexOutput};
+
+// where a shader fetches its vertices and instances from. implemented for MeshData, or for a
+// custom uniform so SourceShader can read it directly
+pub trait VertexSource {
+    type Vertex;
+
+    fn vertex(&self, vertex_id: usize) -> &Self::Vertex;
+}
+
+pub trait InstanceSource {
+    type Instance;
+
+    fn instance(&self, instance_id: usize) -> &Self::Instance;
+}
+
+impl<V> VertexSource for Vec<V> {
+    type Vertex = V;
+
+    fn vertex(&self, vertex_id: usize) -> &V {
+        &self[vertex_id]
+    }
+}
+
+impl<I> InstanceSource for Vec<I> {
+    type Instance = I;
+
+    fn instance(&self, instance_id: usize) -> &I {
+        &self[instance_id]
+    }
+}
+
+#[derive(Debug)]
+pub enum VertexBufferError {
+    OutOfRange { index: usize, len: usize },
+}
+
+impl Display for VertexBufferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange { index, len } => write!(
+                f,
+                "Vertex {} is out of range for a buffer of {} vertices!",
+                index, len
+            ),
+        }
+    }
+}
+
+impl Error for VertexBufferError {}
+
+// vertices meant to be stored in a uniform and fetched with get from the vertex stage. pass len
+// to IndexedRenderCallBuilder::vertex_count to have bad indices rejected before any shading
+pub struct VertexBuffer<V> {
+    vertices: Vec<V>,
+}
+
+impl<V> VertexBuffer<V> {
+    pub fn new(vertices: Vec<V>) -> VertexBuffer<V> {
+        VertexBuffer { vertices }
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn get(&self, vertex_id: usize) -> Result<&V, VertexBufferError> {
+        self.vertices
+            .get(vertex_id)
+            .ok_or(VertexBufferError::OutOfRange {
+                index: vertex_id,
+                len: self.vertices.len(),
+            })
+    }
+
+    // a single attribute of a vertex, e.g. vbo.attribute(id, |v| v.position)
+    pub fn attribute<A, F>(&self, vertex_id: usize, attribute: F) -> Result<A, VertexBufferError>
+    where
+        F: FnOnce(&V) -> A,
+    {
+        self.get(vertex_id).map(attribute)
+    }
+
+    pub fn vertices(&self) -> &[V] {
+        &self.vertices
+    }
+}
+
+impl<V> From<Vec<V>> for VertexBuffer<V> {
+    fn from(vertices: Vec<V>) -> VertexBuffer<V> {
+        VertexBuffer::new(vertices)
+    }
+}
+
+// panics with the error's message rather than a bare index panic
+impl<V> VertexSource for VertexBuffer<V> {
+    type Vertex = V;
+
+    fn vertex(&self, vertex_id: usize) -> &V {
+        self.get(vertex_id)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+// the common case of a vertex buffer and an instance buffer, usable as SourceShader's uniform
+pub struct MeshData<V, I> {
+    pub vertices: Vec<V>,
+    pub instances: Vec<I>,
+}
+
+impl<V, I> VertexSource for MeshData<V, I> {
+    type Vertex = V;
+
+    fn vertex(&self, vertex_id: usize) -> &V {
+        self.vertices.vertex(vertex_id)
+    }
+}
+
+impl<V, I> InstanceSource for MeshData<V, I> {
+    type Instance = I;
+
+    fn instance(&self, instance_id: usize) -> &I {
+        self.instances.instance(instance_id)
+    }
+}
+
+// a Shader built from two closures, for uniforms that are nothing but vertex and instance data.
+// vertex is called with the fetched vertex and instance, fragment with the interpolated working
+// data and the instance
+pub struct SourceShader<U, W, VF, FF> {
+    vertex: VF,
+    fragment: FF,
+
+    _marker: PhantomData<fn(&U) -> W>,
+}
+
+impl<U, W, VF, FF> SourceShader<U, W, VF, FF>
+where
+    U: VertexSource + InstanceSource,
+    VF: Fn(&U::Vertex, &U::Instance) -> VertexOutput<W>,
+    FF: Fn(&W, &U::Instance) -> u32,
+{
+    pub fn new(vertex: VF, fragment: FF) -> SourceShader<U, W, VF, FF> {
+        SourceShader {
+            vertex,
+            fragment,
+
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<U, W, VF, FF> Shader for SourceShader<U, W, VF, FF>
+where
+    U: VertexSource + InstanceSource + Sync,
+    W: Blendable + Sync,
+    VF: Fn(&U::Vertex, &U::Instance) -> VertexOutput<W>,
+    FF: Fn(&W, &U::Instance) -> u32,
+{
+    type Uniform = U;
+    type Working = W;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        (self.vertex)(
+            context.data.vertex(context.vertex_id),
+            context.data.instance(context.instance_id),
+        )
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        (self.fragment)(&context.working, context.data.instance(context.instance_id))
+    }
+}
@@ -0,0 +1,124 @@
+#[derive(Debug, Clone, Copy)]
+pub enum Topology {
+    TriangleList,
+
+    // every index forms a triangle with the two before it. winding is kept consistent by
+    // swapping the first two vertices of every other triangle
+    TriangleStrip,
+
+    // every index forms a triangle with the one before it and the first index
+    TriangleFan,
+
+    LineList,
+
+    // every index is connected to the one before it
+    LineStrip,
+
+    // same as LineStrip, plus a segment closing the last index back to the first
+    LineLoop,
+
+    // every index is expanded into a screen-aligned quad of the pipeline's point size
+    PointList,
+}
+
+// offsets into the index buffer of the vertices making up a single primitive
+pub(crate) enum Primitive {
+    Triangle([usize; 3]),
+    Line([usize; 2]),
+    Point([usize; 1]),
+}
+
+impl Primitive {
+    pub(crate) fn offsets(&self) -> &[usize] {
+        match self {
+            Primitive::Triangle(offsets) => offsets,
+            Primitive::Line(offsets) => offsets,
+            Primitive::Point(offsets) => offsets,
+        }
+    }
+}
+
+impl Topology {
+    // splits the index buffer into runs at every occurrence of the restart index, assembling
+    // each run independently
+    pub(crate) fn assemble(
+        &self,
+        indices: &[u16],
+        primitive_restart: Option<u16>,
+    ) -> Vec<Primitive> {
+        let mut primitives = Vec::new();
+        let mut run_start = 0;
+
+        let restarts = indices
+            .iter()
+            .enumerate()
+            .filter(|(_, index)| Some(**index) == primitive_restart)
+            .map(|(i, _)| i);
+
+        for run_end in restarts.chain([indices.len()]) {
+            self.assemble_run(run_start, run_end - run_start, &mut primitives);
+            run_start = run_end + 1;
+        }
+
+        primitives
+    }
+
+    // indices at the end of each run that don't complete a primitive. only lists can have any,
+    // strips, fans and loops with too few indices simply assemble nothing
+    pub(crate) fn trailing_indices(
+        &self,
+        indices: &[u16],
+        primitive_restart: Option<u16>,
+    ) -> usize {
+        let per_primitive = match self {
+            Topology::TriangleList => 3,
+            Topology::LineList => 2,
+            _ => return 0,
+        };
+
+        indices
+            .split(|index| Some(*index) == primitive_restart)
+            .map(|run| run.len() % per_primitive)
+            .sum()
+    }
+
+    fn assemble_run(&self, offset: usize, count: usize, primitives: &mut Vec<Primitive>) {
+        let first_primitive = primitives.len();
+
+        match self {
+            Topology::TriangleList => primitives
+                .extend((0..count / 3).map(|i| Primitive::Triangle([i * 3, i * 3 + 1, i * 3 + 2]))),
+            Topology::TriangleStrip => primitives.extend((2..count).map(|i| match i % 2 {
+                0 => Primitive::Triangle([i - 2, i - 1, i]),
+                _ => Primitive::Triangle([i - 1, i - 2, i]),
+            })),
+            Topology::TriangleFan => {
+                primitives.extend((2..count).map(|i| Primitive::Triangle([0, i - 1, i])))
+            }
+            Topology::LineList => {
+                primitives.extend((0..count / 2).map(|i| Primitive::Line([i * 2, i * 2 + 1])))
+            }
+            Topology::LineStrip => {
+                primitives.extend((1..count).map(|i| Primitive::Line([i - 1, i])))
+            }
+            Topology::LineLoop => {
+                primitives.extend((1..count).map(|i| Primitive::Line([i - 1, i])));
+
+                // two indices already form a closed loop
+                if count > 2 {
+                    primitives.push(Primitive::Line([count - 1, 0]));
+                }
+            }
+            Topology::PointList => primitives.extend((0..count).map(|i| Primitive::Point([i]))),
+        }
+
+        // offsets above are relative to the start of the run
+        for primitive in &mut primitives[first_primitive..] {
+            match primitive {
+                Primitive::Triangle(offsets) => offsets.iter_mut().for_each(|o| *o += offset),
+                Primitive::Line(offsets) => offsets.iter_mut().for_each(|o| *o += offset),
+                Primitive::Point(offsets) => offsets.iter_mut().for_each(|o| *o += offset),
+            }
+        }
+    }
+}
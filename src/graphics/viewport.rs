@@ -0,0 +1,93 @@
+use nalgebra::Point2;
+
+use super::scissor::Scissor;
+
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+
+    pub width: usize,
+    pub height: usize,
+
+    // fragments with a recovered depth outside of this range are discarded. the z of
+    // FragmentContext::frag_coord is the depth mapped from this range to [0, 1]
+    pub min_depth: f32,
+    pub max_depth: f32,
+}
+
+impl Viewport {
+    pub fn full(width: usize, height: usize) -> Viewport {
+        Viewport {
+            x: 0,
+            y: 0,
+
+            width,
+            height,
+
+            min_depth: 0.0,
+            max_depth: f32::INFINITY,
+        }
+    }
+
+    pub fn to_scissor(&self) -> Scissor {
+        Scissor {
+            x: self.x,
+            y: self.y,
+
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub(crate) fn contains_depth(&self, depth: f32) -> bool {
+        depth >= self.min_depth && depth <= self.max_depth
+    }
+
+    // maps a depth in this viewport's range to [0, 1] like a window depth. a finite range maps
+    // linearly, while an infinite far plane maps one unit past min_depth to 0.5 and only reaches 1
+    // at infinity. an infinite min_depth counts from zero instead
+    pub(crate) fn window_depth(&self, depth: f32) -> f32 {
+        let near = match self.min_depth.is_finite() {
+            true => self.min_depth,
+            false => 0.0,
+        };
+
+        let distance = (depth - near).max(0.0);
+        if self.max_depth.is_finite() {
+            let range = self.max_depth - near;
+            match range > 0.0 {
+                true => (distance / range).min(1.0),
+                false => 0.0,
+            }
+        } else {
+            distance / (distance + 1.0)
+        }
+    }
+
+    // maps the center of the framebuffer pixel (x, y) into the NDC space of this viewport
+    pub(crate) fn pixel_to_ndc(&self, x: usize, y: usize) -> Point2<f32> {
+        Point2::new(self.pixel_x_to_ndc(x), self.pixel_y_to_ndc(y))
+    }
+
+    pub(crate) fn pixel_x_to_ndc(&self, x: usize) -> f32 {
+        let local_x = x as f32 - self.x as f32 + 0.5;
+        ((local_x / self.width as f32) * 2.0) - 1.0
+    }
+
+    pub(crate) fn pixel_y_to_ndc(&self, y: usize) -> f32 {
+        let local_y = y as f32 - self.y as f32 + 0.5;
+        ((local_y / self.height as f32) * 2.0) - 1.0
+    }
+
+    // rounds an NDC position to the nearest 1 / 2^bits of a pixel
+    pub(crate) fn snap_ndc(&self, point: Point2<f32>, bits: u8) -> Point2<f32> {
+        let steps = (1u32 << bits) as f32;
+        let snap = |ndc: f32, size: usize| {
+            let pixels = (ndc + 1.0) / 2.0 * size as f32;
+            (pixels * steps).round() / steps / size as f32 * 2.0 - 1.0
+        };
+
+        Point2::new(snap(point.x, self.width), snap(point.y, self.height))
+    }
+}
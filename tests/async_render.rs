@@ -0,0 +1,124 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use rast::graphics::*;
+
+use common::*;
+
+fn async_call(
+    pipeline: &Arc<Pipeline<FlatShader>>,
+    indices: Vec<u16>,
+    uniforms: FlatUniforms,
+) -> AsyncRenderCall<FlatShader> {
+    AsyncRenderCall {
+        pipeline: pipeline.clone(),
+        vertex_offset: 0,
+        first_instance: 0,
+        instance_count: 1,
+        scissor: None,
+        use_user_scissor_only: false,
+        render_area: None,
+        viewports: Vec::new(),
+        indices,
+        vertex_count: None,
+        primitive_restart: None,
+        flip_winding: false,
+        clip_planes: Vec::new(),
+        depth_source: None,
+        data: Arc::new(uniforms),
+    }
+}
+
+#[test]
+fn async_renders_to_separate_targets() {
+    let mut rast = Rasterizer::new();
+    let pipeline = Arc::new(Pipeline::builder(FlatShader {}).build().unwrap());
+
+    let mut handles = Vec::new();
+    let mut targets = Vec::new();
+    for color in [0xFF0000FF, 0x00FF00FF] {
+        let framebuffer = Arc::new(Mutex::new(cleared_framebuffer(16, 16, false)));
+        rast.push_render_target(framebuffer.clone()).unwrap();
+
+        let uniforms = FlatUniforms {
+            vertices: fullscreen(0.5),
+            color,
+        };
+
+        handles.push(
+            rast.render_indexed_async(async_call(&pipeline, vec![0, 1, 2], uniforms))
+                .unwrap(),
+        );
+
+        rast.pop_render_target().unwrap();
+        targets.push((framebuffer, color));
+    }
+
+    for handle in handles {
+        handle.join(&mut rast).unwrap();
+    }
+
+    for (framebuffer, color) in targets {
+        assert!(color_data(&framebuffer).iter().all(|pixel| *pixel == color));
+    }
+
+    assert_eq!(rast.stats().calls, 2);
+}
+
+#[test]
+fn panicking_render_thread_is_an_error() {
+    let (mut rast, _framebuffer) = target(4, 4, false);
+    let pipeline = Arc::new(Pipeline::builder(FlatShader {}).build().unwrap());
+
+    // index 3 is past the end of the vertices, and without a vertex count nothing catches it
+    // before the vertex stage
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFFFFFFFF,
+    };
+
+    let handle = rast
+        .render_indexed_async(async_call(&pipeline, vec![0, 1, 3], uniforms))
+        .unwrap();
+
+    assert!(matches!(
+        handle.join(&mut rast),
+        Err(RasterizerError::RenderThreadPanicked)
+    ));
+}
+
+#[test]
+fn async_render_tests_against_the_depth_source() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {})
+        .depth(DepthMode::Test)
+        .depth_compare(DepthCompare::Less)
+        .build()
+        .unwrap();
+
+    // the left half of the scene is in front of the triangle
+    let mut scene = Image::new(4, 4);
+    for (x, y) in scene.coordinates() {
+        scene.exchange(x, y, if x < 2 { 0.25 } else { 1.0 });
+    }
+
+    let scene = Arc::new(scene);
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let call = AsyncRenderCall {
+        depth_source: Some(scene.clone()),
+        ..async_call(&Arc::new(pipeline), vec![0, 1, 2], uniforms)
+    };
+
+    rast.render_indexed_async(call)
+        .unwrap()
+        .join(&mut rast)
+        .unwrap();
+
+    assert_eq!(pixel(&framebuffer, 0, 1), CLEAR_COLOR);
+    assert_eq!(pixel(&framebuffer, 3, 1), 0xFF0000FF);
+}
@@ -0,0 +1,55 @@
+use nalgebra::{Point3, Vector3};
+
+use rast::graphics::*;
+
+// blend3 is a shortcut for blend with three values, so the two have to agree exactly
+#[test]
+fn blend3_matches_blend() {
+    let data: [(Point3<f32>, u32, f32); 3] = [
+        (Point3::new(0.0, 1.0, 2.0), 0xFF0000FF, 1.0),
+        (Point3::new(3.0, 1.0, 2.0), 0x00FF0080, 2.0),
+        (Point3::new(0.0, 5.0, 2.0), 0x0000FFFF, 3.0),
+    ];
+
+    let [a, b, c] = &data;
+    let weights = [0.2, 0.3, 0.5];
+
+    assert_eq!(
+        <(Point3<f32>, u32, f32)>::blend(&[a, b, c], &weights),
+        <(Point3<f32>, u32, f32)>::blend3(a, weights[0], b, weights[1], c, weights[2])
+    );
+
+    assert_eq!(
+        u32::blend(&[&1, &2, &3], &weights),
+        u32::blend3(&1, weights[0], &2, weights[1], &3, weights[2])
+    );
+}
+
+// a normal and a scalar interpolated together, the way a shader would carry both without a named
+// struct. at the barycenter each element is the plain average
+#[test]
+fn tuples_blend_each_element() {
+    let data: [(Vector3<f32>, f32); 3] = [
+        (Vector3::new(3.0, 0.0, 0.0), 0.25),
+        (Vector3::new(0.0, 3.0, 0.0), 0.5),
+        (Vector3::new(0.0, 0.0, 3.0), 0.75),
+    ];
+
+    let [a, b, c] = &data;
+    let third = 1.0 / 3.0;
+
+    let (normal, depth) = <(Vector3<f32>, f32)>::blend3(a, third, b, third, c, third);
+    assert!(
+        (normal - Vector3::new(1.0, 1.0, 1.0)).norm() < 1e-6,
+        "{normal}"
+    );
+    assert!((depth - 0.5).abs() < 1e-6, "{depth}");
+
+    // more elements than the rasterizer ever blends at once takes the same path as the rest
+    let (normal, depth) = <(Vector3<f32>, f32)>::blend(&[a, b, c, a, b], &[0.2; 5]);
+    assert!(
+        (normal - Vector3::new(1.2, 1.2, 0.6)).norm() < 1e-6,
+        "{normal}"
+    );
+    assert!((depth - 0.45).abs() < 1e-6, "{depth}");
+}
@@ -0,0 +1,445 @@
+mod common;
+
+use std::array;
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+// a vertex at depth zero has an infinite reciprocal, which turns the perspective correct weights
+// into nan
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "Non-finite blend weights")]
+fn broken_vertex_depth_trips_the_weight_assertion() {
+    let (mut rast, _framebuffer) = target(8, 8, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.0),
+            Point3::new(3.0, -1.0, 0.5),
+            Point3::new(-1.0, 3.0, 0.5),
+        ],
+        color: 0xFF0000FF,
+    };
+
+    // the default viewport would discard the broken depths before the fragment is shaded
+    let viewports = [Viewport {
+        min_depth: f32::NEG_INFINITY,
+        ..Viewport::full(8, 8)
+    }];
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .viewports(&viewports)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+}
+
+// each src-over blend covers half of whats left, so 32 of them should leave the destination alpha
+// indistinguishable from opaque
+#[test]
+fn repeated_src_over_accumulates_alpha() {
+    let mut framebuffer = Framebuffer::new(4, 4, 1, false);
+    framebuffer
+        .clear(&ClearValue {
+            color: 0x00000000,
+            depth: 1.0,
+        })
+        .unwrap();
+
+    let framebuffer = Arc::new(Mutex::new(framebuffer));
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(framebuffer.clone()).unwrap();
+
+    let over = |src_factor| ComponentBlendOp {
+        op: BlendOp::Add,
+        src_factor,
+        dst_factor: BlendFactor::OneMinusSrcAlpha,
+    };
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .blending(vec![BlendAttachment {
+            color: Some(over(BlendFactor::SrcAlpha)),
+            alpha: Some(over(BlendFactor::One)),
+        }])
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFFFFFF80,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    for _ in 0..32 {
+        rast.render_indexed(&call).unwrap();
+    }
+
+    let alpha = (pixel(&framebuffer, 1, 1) & 0xFF) as f32 / 255.0;
+    assert!(alpha > 0.995, "alpha only reached {alpha}");
+}
+
+// only the first attachment blends, the second is overwritten
+#[test]
+fn none_entries_leave_an_attachment_unblended() {
+    let pipeline = Pipeline::builder(FlatShader {})
+        .blending(vec![Some(src_over()), None])
+        .color_attachments(2)
+        .build()
+        .unwrap();
+
+    let mut framebuffer = Framebuffer::new(4, 4, 2, false);
+    framebuffer
+        .clear(&ClearValue {
+            color: 0x0000FFFF,
+            depth: 1.0,
+        })
+        .unwrap();
+
+    let framebuffer = Arc::new(Mutex::new(framebuffer));
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(framebuffer.clone()).unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF000080,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    let framebuffer = framebuffer.lock().unwrap();
+    let [blended, overwritten] =
+        [0, 1].map(|i| *framebuffer.color_attachments()[i].at(1, 1).unwrap());
+
+    // half red over blue
+    let [r, g, b, _] = blended.to_be_bytes();
+    assert!(
+        r.abs_diff(0x80) <= 1 && g == 0 && b.abs_diff(0x7F) <= 1,
+        "{blended:08X}"
+    );
+    assert_eq!(overwritten, 0xFF000080);
+}
+
+#[test]
+fn blend_entries_must_match_the_attachment_count() {
+    let result = Pipeline::builder(FlatShader {})
+        .blending(vec![Some(src_over()), None])
+        .color_attachments(3)
+        .build();
+
+    assert!(matches!(
+        result,
+        Err(PipelineError::BlendAttachmentCountMismatch {
+            expected: 3,
+            actual: 2
+        })
+    ));
+}
+
+// a fully transparent src-over fragment leaves the color alone either way, the modes differ in
+// whether the blend runs and whether depth is still written
+#[test]
+fn alpha_zero_discard_skips_the_store() {
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF000000,
+    };
+
+    for (mode, color, depth) in [
+        (AlphaZeroDiscard::Off, 0x33669900, 0.5),
+        (AlphaZeroDiscard::ColorOnly, 0x336699CC, 0.5),
+        (AlphaZeroDiscard::ColorAndDepth, 0x336699CC, 1.0),
+    ] {
+        let pipeline = Pipeline::builder(FlatShader {})
+            .depth(DepthMode::Write)
+            .blending(vec![src_over()])
+            .alpha_zero_discard(mode)
+            .build()
+            .unwrap();
+
+        let mut framebuffer = Framebuffer::new(2, 2, 1, true);
+        framebuffer
+            .clear(&ClearValue {
+                color: 0x336699CC,
+                depth: 1.0,
+            })
+            .unwrap();
+
+        let framebuffer = Arc::new(Mutex::new(framebuffer));
+        let mut rast = Rasterizer::new();
+        rast.push_render_target(framebuffer.clone()).unwrap();
+
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+        rast.render_indexed(&call).unwrap();
+
+        // the blend without a discard writes the source alpha into the destination
+        assert_eq!(pixel(&framebuffer, 0, 0), color, "{mode:?}");
+
+        let framebuffer = framebuffer.lock().unwrap();
+        let depth_attachment = framebuffer.depth_attachment().as_ref().unwrap();
+        assert_eq!(depth_attachment.at(0, 0).unwrap(), depth, "{mode:?}");
+    }
+}
+
+// additive blending isn't a no-op at zero alpha, so its fragments are never discarded
+#[test]
+fn alpha_zero_discard_only_applies_to_src_over() {
+    let (mut rast, framebuffer) = target(2, 2, false);
+    let additive = BlendAttachment {
+        color: Some(ComponentBlendOp {
+            op: BlendOp::Add,
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+        }),
+        alpha: None,
+    };
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .blending(vec![additive])
+        .alpha_zero_discard(AlphaZeroDiscard::ColorAndDepth)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF000000,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    // red is added to the black destination, and alpha without a blend op takes the source's
+    assert_eq!(pixel(&framebuffer, 0, 0), 0xFF000000);
+}
+
+// darkens in the fragment stage by reading the destination itself
+struct DarkenShader {}
+
+impl Shader for DarkenShader {
+    type Uniform = FlatUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        let dst = unpack_channels(context.dst_color.unwrap());
+        let src = unpack_channels(context.data.color);
+        pack_channels(array::from_fn(|i| src[i].min(dst[i])))
+    }
+}
+
+// draws the left half in one color over the clear color, then darkens everything with another
+fn render_darkened<T: Shader<Uniform = FlatUniforms> + Sync>(darken: &Pipeline<T>) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(8, 8, false);
+
+    let flat = Pipeline::builder(FlatShader {}).build().unwrap();
+    let left_half = FlatUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.5),
+            Point3::new(0.0, -1.0, 0.5),
+            Point3::new(0.0, 1.0, 0.5),
+            Point3::new(-1.0, 1.0, 0.5),
+        ],
+        color: 0x80FF40FF,
+    };
+
+    let call = IndexedRenderCall::builder(&flat, &[0, 1, 2, 0, 2, 3], &left_half).build();
+    rast.render_indexed(&call).unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF206080,
+    };
+
+    rast.render_indexed(&IndexedRenderCall::builder(darken, &[0, 1, 2], &uniforms).build())
+        .unwrap();
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn shader_darken_matches_min_blending() {
+    let programmable = Pipeline::builder(DarkenShader {})
+        .read_dst(true)
+        .build()
+        .unwrap();
+
+    let min = ComponentBlendOp {
+        op: BlendOp::Min,
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+    };
+
+    let fixed_function = Pipeline::builder(FlatShader {})
+        .blending(vec![BlendAttachment {
+            color: Some(min.clone()),
+            alpha: Some(min),
+        }])
+        .build()
+        .unwrap();
+
+    let expected = render_darkened(&fixed_function);
+    assert_eq!(expected[0], 0x80204080);
+    assert_eq!(expected[7], 0x00000080);
+
+    assert_eq!(render_darkened(&programmable), expected);
+}
+
+// outputs the uniform color along with a per channel coverage mask as the secondary color
+struct CoverageShader {
+    coverage: u32,
+}
+
+impl Shader for CoverageShader {
+    type Uniform = FlatUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.color
+    }
+
+    fn fragment_stage_dual(
+        &self,
+        context: &FragmentContext<Self::Uniform, Self::Working>,
+    ) -> (u32, Option<u32>) {
+        (context.data.color, Some(self.coverage))
+    }
+}
+
+#[test]
+fn dual_source_coverage_modulates_the_destination() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    rast.clear(&ClearValue {
+        color: 0x0000FFFF,
+        depth: 1.0,
+    })
+    .unwrap();
+
+    // src * coverage + dst * (1 - coverage), per channel
+    let pipeline = Pipeline::builder(CoverageShader {
+        coverage: 0xFF800040,
+    })
+    .blending(vec![BlendAttachment {
+        color: Some(ComponentBlendOp {
+            op: BlendOp::Add,
+            src_factor: BlendFactor::Src1Color,
+            dst_factor: BlendFactor::OneMinusSrc1Color,
+        }),
+        alpha: Some(ComponentBlendOp {
+            op: BlendOp::Add,
+            src_factor: BlendFactor::Src1Alpha,
+            dst_factor: BlendFactor::OneMinusSrc1Alpha,
+        }),
+    }])
+    .build()
+    .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFFFFFF00,
+    };
+
+    rast.render_indexed(&IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build())
+        .unwrap();
+
+    // red fully covered, green half, blue not at all, and a quarter of the alpha
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == 0xFF80FFBF)
+    );
+}
+
+// draws color over a framebuffer cleared to clear, blending only the color channels
+fn blend_fullscreen(clear: u32, color: u32, op: ComponentBlendOp) -> u32 {
+    let (mut rast, framebuffer) = target(2, 2, false);
+    rast.clear(&ClearValue {
+        color: clear,
+        depth: 1.0,
+    })
+    .unwrap();
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .blending(vec![BlendAttachment {
+            color: Some(op),
+            alpha: None,
+        }])
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color,
+    };
+
+    rast.render_indexed(&IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build())
+        .unwrap();
+
+    pixel(&framebuffer, 0, 0)
+}
+
+// the color factors scale each channel by the matching channel, unlike the scalar alpha factors
+#[test]
+fn color_factors_multiply_and_screen() {
+    let multiply = ComponentBlendOp {
+        op: BlendOp::Add,
+        src_factor: BlendFactor::DstColor,
+        dst_factor: BlendFactor::Zero,
+    };
+
+    assert_eq!(
+        blend_fullscreen(0xFF8040FF, 0x80FF00FF, multiply),
+        0x808000FF
+    );
+
+    let screen = ComponentBlendOp {
+        op: BlendOp::Add,
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::OneMinusSrcColor,
+    };
+
+    // 1 - (1 - src) * (1 - dst) per channel
+    let [r, g, b, _] = blend_fullscreen(0x808000FF, 0x80FF00FF, screen).to_be_bytes();
+    assert!(
+        r.abs_diff(0xC0) <= 1 && g == 0xFF && b == 0,
+        "{r:02X}{g:02X}{b:02X}"
+    );
+}
+
+// white plus white is 2 in every channel, which has to saturate to white rather than wrap
+#[test]
+fn additive_overflow_clamps_to_white() {
+    let additive = ComponentBlendOp {
+        op: BlendOp::Add,
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+    };
+
+    assert_eq!(
+        blend_fullscreen(0xFFFFFFFF, 0xFFFFFFFF, additive.clone()),
+        0xFFFFFFFF
+    );
+
+    // channels only saturate where they overflow, and alpha without a blend op is the source's
+    assert_eq!(
+        blend_fullscreen(0xC04000FF, 0x80400000, additive),
+        0xFF800000
+    );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::collections::BTreeSet;
+
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use rast::graphics::*;
+
+use common::*;
+
+struct CubeShader {}
+
+struct CubeUniforms {
+    mvp: Matrix4<f32>,
+    vertices: Vec<Point3<f32>>,
+    color: u32,
+}
+
+impl Shader for CubeShader {
+    type Uniform = CubeUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let clip = context.data.mvp * context.data.vertices[context.vertex_id].to_homogeneous();
+        VertexOutput {
+            position: clip_to_vertex_position(&clip),
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.color
+    }
+}
+
+const FACE_NORMALS: [Vector3<f32>; 6] = [
+    Vector3::new(1.0, 0.0, 0.0),
+    Vector3::new(-1.0, 0.0, 0.0),
+    Vector3::new(0.0, 1.0, 0.0),
+    Vector3::new(0.0, -1.0, 0.0),
+    Vector3::new(0.0, 0.0, 1.0),
+    Vector3::new(0.0, 0.0, -1.0),
+];
+
+// the corners of a unit cube face, counterclockwise when looking at it from outside the cube
+fn face_vertices(normal: &Vector3<f32>) -> Vec<Point3<f32>> {
+    let u = match normal.x.abs() > 0.5 {
+        true => Vector3::y(),
+        false => Vector3::x(),
+    };
+
+    // u cross v points along the normal
+    let v = normal.cross(&u);
+    let corner = |a: f32, b: f32| Point3::from((normal + u * a + v * b) * 0.5);
+
+    vec![
+        corner(-1.0, -1.0),
+        corner(1.0, -1.0),
+        corner(1.0, 1.0),
+        corner(-1.0, 1.0),
+    ]
+}
+
+// draws every face of the cube in its own color and returns which faces are visible
+fn visible_faces(mvp: Matrix4<f32>, winding: WindingOrder) -> BTreeSet<usize> {
+    let (mut rast, framebuffer) = target(64, 64, false);
+    let pipeline = Pipeline::builder(CubeShader {})
+        .cull_back(true)
+        .winding_order(winding)
+        .build()
+        .unwrap();
+
+    for (i, normal) in FACE_NORMALS.iter().enumerate() {
+        let uniforms = CubeUniforms {
+            mvp,
+            vertices: face_vertices(normal),
+            color: ((i as u32 + 1) << 8) | 0xFF,
+        };
+
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 0, 2, 3], &uniforms).build();
+        rast.render_indexed(&call).unwrap();
+    }
+
+    color_data(&framebuffer)
+        .into_iter()
+        .filter(|color| *color != CLEAR_COLOR)
+        .map(|color| (color >> 8) as usize - 1)
+        .collect()
+}
+
+// looking from the +x, +y, +z corner, only those three faces point at the camera
+#[test]
+fn cube_shows_only_front_faces() {
+    let eye = Point3::new(1.5, 2.0, 3.0);
+    let front_faces = BTreeSet::from([0, 2, 4]);
+
+    let rh =
+        perspective_rh(1.0, 1.0, 0.1, 100.0) * look_at_rh(&eye, &Point3::origin(), &Vector3::y());
+    assert_eq!(
+        visible_faces(rh, WindingOrder::CounterClockwise),
+        front_faces
+    );
+
+    // the left handed helpers mirror z, which flips the winding of every face on screen
+    let lh =
+        perspective_lh(1.0, 1.0, 0.1, 100.0) * look_at_lh(&eye, &Point3::origin(), &Vector3::y());
+    assert_eq!(visible_faces(lh, WindingOrder::Clockwise), front_faces);
+}
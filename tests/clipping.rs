@@ -0,0 +1,72 @@
+mod common;
+
+use nalgebra::{Point3, Vector4};
+
+use rast::graphics::*;
+
+use common::*;
+
+// clips against the vertex positions themselves, so planes are in NDC
+struct NdcClipShader {}
+
+impl Shader for NdcClipShader {
+    type Uniform = FlatUniforms;
+    type Working = Point3<f32>;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let position = context.data.vertices[context.vertex_id];
+        VertexOutput {
+            position,
+            data: position,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.color
+    }
+
+    fn clip_position(&self, working: &Self::Working) -> Option<Point3<f32>> {
+        Some(*working)
+    }
+}
+
+fn render_clipped(planes: &[Vector4<f32>], tile_height: Option<usize>) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(8, 8, false);
+    let pipeline = Pipeline::builder(NdcClipShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .clip_planes(planes)
+        .build();
+
+    match tile_height {
+        Some(height) => rast.render_indexed_tiled(&call, height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    color_data(&framebuffer)
+}
+
+// keeping x >= 0 leaves exactly the right half of the screen
+#[test]
+fn clip_plane_cuts_a_triangle_in_half() {
+    let planes = [Vector4::new(1.0, 0.0, 0.0, 0.0)];
+
+    let unclipped = render_clipped(&[], None);
+    assert!(unclipped.iter().all(|color| *color == 0xFF0000FF));
+
+    let clipped = render_clipped(&planes, None);
+    for (i, color) in clipped.iter().enumerate() {
+        let expected = match i % 8 < 4 {
+            true => CLEAR_COLOR,
+            false => 0xFF0000FF,
+        };
+
+        assert_eq!(*color, expected, "pixel ({}, {})", i % 8, i / 8);
+    }
+
+    assert_eq!(render_clipped(&planes, Some(3)), clipped);
+}
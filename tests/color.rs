@@ -0,0 +1,61 @@
+use rast::graphics::*;
+
+#[test]
+fn rgba8_round_trips_through_u32() {
+    let color = RGBA8::from_rgba(0x12, 0x34, 0x56, 0x78);
+    assert_eq!(color.to_u32(), 0x12345678);
+    assert_eq!(
+        (color.r(), color.g(), color.b(), color.a()),
+        (0x12, 0x34, 0x56, 0x78)
+    );
+
+    let unpacked = RGBA8::from(0xFF8000C0);
+    assert_eq!(unpacked, RGBA8::from_rgba(0xFF, 0x80, 0x00, 0xC0));
+    assert_eq!(u32::from(unpacked), 0xFF8000C0);
+}
+
+// mean error of a red gradient stored in the format and read back
+fn gradient_error(format: PixelFormat) -> f32 {
+    let steps = 1000;
+    let total: f32 = (0..=steps)
+        .map(|i| {
+            let value = i as f32 / steps as f32;
+            let stored = format.encode([value, 0.0, 0.0, 1.0]);
+            (format.decode(stored)[0] - value).abs()
+        })
+        .sum();
+
+    total / (steps + 1) as f32
+}
+
+#[test]
+fn rgb10a2_keeps_more_precision_than_rgba8() {
+    let format = PixelFormat::Rgb10A2;
+    assert_eq!(format.encode([1.0; 4]), u32::MAX);
+    assert_eq!(format.decode(u32::MAX), [1.0; 4]);
+    assert_eq!(format.encode([0.0; 4]), 0);
+
+    // four times the steps, so roughly a quarter of the error
+    let error_8 = gradient_error(PixelFormat::Rgba8);
+    let error_10 = gradient_error(PixelFormat::Rgb10A2);
+    assert!(error_10 < error_8 / 3.0, "{error_10} vs {error_8}");
+
+    assert_eq!(PixelFormat::Rgba8.convert(0xFF0000FF, format), 0xFFC00003);
+}
+
+#[test]
+fn channels_round_trip_exactly() {
+    let black = 0x000000FF;
+    let white = 0xFFFFFFFF;
+    let gray = 0x808080FF;
+
+    assert_eq!(unpack_channels(black), [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(unpack_channels(white), [1.0; 4]);
+
+    for color in [black, white, gray, 0x7F7F7F80] {
+        assert_eq!(pack_channels(unpack_channels(color)), color);
+    }
+
+    // out of range channels saturate, and nan becomes 0
+    assert_eq!(pack_channels([2.0, -1.0, f32::NAN, 1.0]), 0xFF0000FF);
+}
@@ -0,0 +1,119 @@
+// helpers shared between the integration tests. not every test file uses all of them
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+// draws every vertex as given, in a single color
+#[derive(Debug, Clone)]
+pub struct FlatShader {}
+
+pub struct FlatUniforms {
+    pub vertices: Vec<Point3<f32>>,
+    pub color: u32,
+}
+
+impl Shader for FlatShader {
+    type Uniform = FlatUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.color
+    }
+}
+
+// draws every instance in its own color, shifted right by `offset` in NDC per instance
+#[derive(Debug, Clone)]
+pub struct InstanceShader {}
+
+pub struct InstanceUniforms {
+    pub vertices: Vec<Point3<f32>>,
+    pub colors: Vec<u32>,
+    pub offset: f32,
+}
+
+impl Shader for InstanceShader {
+    type Uniform = InstanceUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let mut position = context.data.vertices[context.vertex_id];
+        position.x += context.instance_id as f32 * context.data.offset;
+
+        VertexOutput {
+            position,
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.colors[context.instance_id]
+    }
+}
+
+pub const CLEAR_COLOR: u32 = 0x000000FF;
+
+// color and depth cleared to CLEAR_COLOR and 1
+pub fn cleared_framebuffer(width: usize, height: usize, depth: bool) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(width, height, 1, depth);
+    framebuffer
+        .clear(&ClearValue {
+            color: CLEAR_COLOR,
+            depth: 1.0,
+        })
+        .unwrap();
+
+    framebuffer
+}
+
+// a cleared framebuffer pushed as the render target of a new rasterizer
+pub fn target(width: usize, height: usize, depth: bool) -> (Rasterizer, Arc<Mutex<Framebuffer>>) {
+    let framebuffer = Arc::new(Mutex::new(cleared_framebuffer(width, height, depth)));
+
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(framebuffer.clone()).unwrap();
+
+    (rast, framebuffer)
+}
+
+pub fn color_data(framebuffer: &Arc<Mutex<Framebuffer>>) -> Vec<u32> {
+    framebuffer.lock().unwrap().color_attachments()[0]
+        .data()
+        .to_vec()
+}
+
+pub fn pixel(framebuffer: &Arc<Mutex<Framebuffer>>, x: usize, y: usize) -> u32 {
+    *framebuffer.lock().unwrap().color_attachments()[0]
+        .at(x, y)
+        .unwrap()
+}
+
+pub fn src_over() -> BlendAttachment {
+    BlendAttachment {
+        color: Some(ComponentBlendOp {
+            src_factor: BlendFactor::SrcAlpha,
+            dst_factor: BlendFactor::OneMinusSrcAlpha,
+            op: BlendOp::Add,
+        }),
+        alpha: None,
+    }
+}
+
+// a triangle covering the whole of NDC
+pub fn fullscreen(z: f32) -> Vec<Point3<f32>> {
+    vec![
+        Point3::new(-1.0, -1.0, z),
+        Point3::new(3.0, -1.0, z),
+        Point3::new(-1.0, 3.0, z),
+    ]
+}
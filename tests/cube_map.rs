@@ -0,0 +1,34 @@
+use nalgebra::Vector3;
+
+use rast::graphics::*;
+
+#[test]
+fn directions_select_their_faces() {
+    let axes = [
+        (Vector3::new(1.0, 0.2, -0.3), CubeFace::PositiveX),
+        (Vector3::new(-1.0, 0.2, -0.3), CubeFace::NegativeX),
+        (Vector3::new(0.3, 1.0, 0.2), CubeFace::PositiveY),
+        (Vector3::new(0.3, -1.0, 0.2), CubeFace::NegativeY),
+        (Vector3::new(-0.2, 0.3, 1.0), CubeFace::PositiveZ),
+        (Vector3::new(-0.2, 0.3, -1.0), CubeFace::NegativeZ),
+    ];
+
+    // each face cleared to its own color, so sampling tells which face was read
+    let cube_map = CubeMap::new(4);
+    for face in CubeFace::ALL {
+        let value = ClearValue {
+            color: (face.index() as u32 + 1) << 8 | 0xFF,
+            depth: 1.0,
+        };
+
+        cube_map.face(face).lock().unwrap().clear(&value).unwrap();
+    }
+
+    for (direction, face) in axes {
+        assert_eq!(CubeFace::from_direction(&direction), face, "{direction:?}");
+        assert_eq!(face.forward().dot(&direction).signum(), 1.0);
+
+        let expected = (face.index() as u32 + 1) << 8 | 0xFF;
+        assert_eq!(cube_map.sample(direction), expected, "{direction:?}");
+    }
+}
@@ -0,0 +1,310 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+// slanted, overlapping triangles, so the depth varies across every face and faces hide each other
+fn depth_scene(color: u32) -> FlatUniforms {
+    FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.9, -0.8, 0.3),
+            Point3::new(0.8, -0.6, 7.0),
+            Point3::new(-0.4, 0.9, 2.5),
+            Point3::new(0.9, 0.9, 0.2),
+            Point3::new(-0.9, 0.1, 9.0),
+            Point3::new(0.3, -0.9, 1.3),
+        ],
+        color,
+    }
+}
+
+#[test]
+fn depth_prepass_passes_equal_everywhere() {
+    const PREPASS: u32 = 0x111111FF;
+    const SHADED: u32 = 0x222222FF;
+
+    let (mut rast, framebuffer) = target(64, 64, true);
+    let indices = [0, 1, 2, 3, 4, 5];
+
+    let prepass = Pipeline::builder(FlatShader {})
+        .depth(DepthMode::Write)
+        .build()
+        .unwrap();
+
+    let prepass_scene = depth_scene(PREPASS);
+    rast.render_indexed(&IndexedRenderCall::builder(&prepass, &indices, &prepass_scene).build())
+        .unwrap();
+
+    let covered = color_data(&framebuffer)
+        .iter()
+        .filter(|pixel| **pixel == PREPASS)
+        .count();
+
+    assert!(covered > 0);
+
+    let shading = Pipeline::builder(FlatShader {})
+        .depth(DepthMode::Test)
+        .depth_compare(DepthCompare::Equal)
+        .build()
+        .unwrap();
+
+    let shading_scene = depth_scene(SHADED);
+    rast.render_indexed(&IndexedRenderCall::builder(&shading, &indices, &shading_scene).build())
+        .unwrap();
+
+    let data = color_data(&framebuffer);
+    assert!(!data.contains(&PREPASS));
+    assert_eq!(
+        data.iter().filter(|pixel| **pixel == SHADED).count(),
+        covered
+    );
+}
+
+// the depth source is only ever read, even by a pipeline that writes depth
+#[test]
+fn depth_source_is_tested_against_but_never_written() {
+    let mut scene = Image::new(4, 4);
+    for (x, y) in scene.coordinates() {
+        scene.exchange(x, y, if x < 2 { 0.25 } else { 1.0 });
+    }
+
+    let before = scene.data().to_vec();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    for depth in [DepthMode::Test, DepthMode::Write] {
+        let (mut rast, framebuffer) = target(4, 4, true);
+        let pipeline = Pipeline::builder(FlatShader {})
+            .depth(depth)
+            .depth_compare(DepthCompare::Less)
+            .build()
+            .unwrap();
+
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+            .depth_source(&scene)
+            .build();
+
+        rast.render_indexed(&call).unwrap();
+
+        assert_eq!(pixel(&framebuffer, 0, 1), CLEAR_COLOR);
+        assert_eq!(pixel(&framebuffer, 3, 1), 0xFF0000FF);
+        assert_eq!(scene.data(), &before[..]);
+    }
+}
+
+#[test]
+fn depth_source_must_match_the_target_size() {
+    let (mut rast, _framebuffer) = target(4, 4, true);
+    let pipeline = Pipeline::builder(FlatShader {})
+        .depth(DepthMode::Test)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let small = Image::new(2, 2);
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .depth_source(&small)
+        .build();
+
+    assert!(matches!(
+        rast.render_indexed(&call),
+        Err(RasterizerError::DepthSourceSizeMismatch)
+    ));
+}
+
+const UNIT_UNORM16: DepthFormat = DepthFormat::Unorm16 {
+    near: 0.0,
+    far: 1.0,
+};
+
+// draws a far and then a near full screen triangle with a Less depth test, returning the color
+// and depth left at a pixel
+fn draw_near_over_far(format: DepthFormat, near: f32, far: f32) -> (u32, f32) {
+    let mut framebuffer = Framebuffer::from_desc(&FramebufferDesc {
+        depth: true,
+        depth_format: format,
+        ..FramebufferDesc::new(4, 4)
+    })
+    .unwrap();
+
+    let clear_depth = match format {
+        DepthFormat::Unorm16 { far, .. } | DepthFormat::Unorm24 { far, .. } => far,
+        DepthFormat::F32 => 1.0,
+    };
+
+    framebuffer
+        .clear(&ClearValue {
+            color: CLEAR_COLOR,
+            depth: clear_depth,
+        })
+        .unwrap();
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .depth(DepthMode::Write)
+        .depth_compare(DepthCompare::Less)
+        .build()
+        .unwrap();
+
+    let mut rast = Rasterizer::new();
+    for (z, color) in [(far, 0xFF0000FF), (near, 0x00FF00FF)] {
+        let uniforms = FlatUniforms {
+            vertices: fullscreen(z),
+            color,
+        };
+
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+        rast.render_indexed_into(&call, &mut framebuffer).unwrap();
+    }
+
+    let color = *framebuffer.color_attachments()[0].at(1, 1).unwrap();
+    let depth = framebuffer
+        .depth_attachment()
+        .as_ref()
+        .unwrap()
+        .at(1, 1)
+        .unwrap();
+    (color, depth)
+}
+
+#[test]
+fn unorm16_depth_orders_surfaces_a_step_apart() {
+    let step = 1.0 / 65535.0;
+    assert_eq!(
+        draw_near_over_far(UNIT_UNORM16, 0.5, 0.5 + 2.0 * step).0,
+        0x00FF00FF
+    );
+
+    // well within a step both quantize to the same depth, so the nearer one fails Less
+    assert_eq!(
+        draw_near_over_far(UNIT_UNORM16, 0.5, 0.5 + 1e-6).0,
+        0xFF0000FF
+    );
+
+    let unit_unorm24 = DepthFormat::Unorm24 {
+        near: 0.0,
+        far: 1.0,
+    };
+
+    for format in [DepthFormat::F32, unit_unorm24] {
+        assert_eq!(draw_near_over_far(format, 0.5, 0.5 + 1e-6).0, 0x00FF00FF);
+    }
+
+    let (_, depth) = draw_near_over_far(UNIT_UNORM16, 0.3, 0.4);
+    assert_eq!(depth, (0.3f32 * 65535.0).round() / 65535.0);
+}
+
+// the range is mapped onto the stored steps, so depths past 1 resolve like any other
+#[test]
+fn unorm16_depth_maps_its_range() {
+    let format = DepthFormat::Unorm16 {
+        near: 1.0,
+        far: 5.0,
+    };
+
+    let step = 4.0 / 65535.0;
+
+    let (color, depth) = draw_near_over_far(format, 2.0, 3.0);
+    assert_eq!(color, 0x00FF00FF);
+    assert!((depth - 2.0).abs() <= step / 2.0, "{depth}");
+
+    // the other way around the farther surface stays in front
+    let (color, depth) = draw_near_over_far(format, 3.0, 2.0);
+    assert_eq!(color, 0xFF0000FF);
+    assert!((depth - 2.0).abs() <= step / 2.0, "{depth}");
+}
+
+// fragments outside of the range are clamped, clearing to them is an error
+#[test]
+fn unorm16_depth_clamps_out_of_range_depths() {
+    assert_eq!(draw_near_over_far(UNIT_UNORM16, 1.5, 2.0).1, 1.0);
+
+    let desc = FramebufferDesc {
+        depth: true,
+        depth_format: UNIT_UNORM16,
+        ..FramebufferDesc::new(4, 4)
+    };
+
+    let mut framebuffer = Framebuffer::from_desc(&desc).unwrap();
+    assert!(matches!(
+        framebuffer.clear(&ClearValue {
+            color: 0,
+            depth: 1.5
+        }),
+        Err(FramebufferError::ClearDepthOutOfRange)
+    ));
+
+    // only 2 bytes of depth per pixel, next to the 4 of the color attachment
+    assert_eq!(framebuffer.memory_bytes(), 4 * 4 * (4 + 2));
+
+    // there's no 3 byte integer to store unorm24 in
+    let unorm24 = Framebuffer::from_desc(&FramebufferDesc {
+        depth_format: DepthFormat::Unorm24 {
+            near: 0.0,
+            far: 1.0,
+        },
+        ..desc.clone()
+    })
+    .unwrap();
+
+    assert_eq!(unorm24.memory_bytes(), 4 * 4 * (4 + 4));
+}
+
+#[test]
+fn depth_formats_need_a_valid_range() {
+    for (near, far) in [
+        (1.0, 1.0),
+        (2.0, 1.0),
+        (0.0, f32::INFINITY),
+        (f32::NAN, 1.0),
+    ] {
+        let desc = FramebufferDesc {
+            depth: true,
+            depth_format: DepthFormat::Unorm16 { near, far },
+            ..FramebufferDesc::new(4, 4)
+        };
+
+        assert!(matches!(
+            Framebuffer::from_desc(&desc),
+            Err(FramebufferError::InvalidDepthFormat)
+        ));
+    }
+}
+
+// changing the format converts the stored depths
+#[test]
+fn setting_the_depth_format_converts_the_attachment() {
+    let mut framebuffer = Framebuffer::new(2, 2, 1, true);
+    framebuffer
+        .clear(&ClearValue {
+            color: 0,
+            depth: 0.3,
+        })
+        .unwrap();
+
+    framebuffer.set_depth_format(UNIT_UNORM16).unwrap();
+    assert_eq!(framebuffer.depth_format(), UNIT_UNORM16);
+
+    let depth = framebuffer.depth_attachment().as_ref().unwrap();
+    assert_eq!(depth.format(), UNIT_UNORM16);
+    assert_eq!(depth.at(1, 1).unwrap(), UNIT_UNORM16.quantize(0.3));
+
+    let invalid = DepthFormat::Unorm16 {
+        near: 1.0,
+        far: 0.0,
+    };
+
+    assert!(matches!(
+        framebuffer.set_depth_format(invalid),
+        Err(FramebufferError::InvalidDepthFormat)
+    ));
+    assert_eq!(framebuffer.depth_format(), UNIT_UNORM16);
+}
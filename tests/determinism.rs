@@ -0,0 +1,62 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+use rayon::ThreadPoolBuilder;
+
+use rast::graphics::*;
+
+use common::*;
+
+// overlapping translucent instances, so any race between faces or rows would change the blend
+fn render_blended_scene() -> Vec<u32> {
+    render_blended_scene_with(Rasterizer::new())
+}
+
+fn render_blended_scene_with(mut rast: Rasterizer) -> Vec<u32> {
+    let framebuffer = Arc::new(Mutex::new(cleared_framebuffer(64, 64, false)));
+    rast.push_render_target(framebuffer.clone()).unwrap();
+
+    // one row per task, to split the rows between as many threads as possible
+    rast.set_scanline_granularity(Some(1));
+
+    let pipeline = Pipeline::builder(InstanceShader {})
+        .blending(vec![src_over()])
+        .color_attachments(1)
+        .build()
+        .unwrap();
+
+    let uniforms = InstanceUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.5),
+            Point3::new(0.2, -0.8, 0.5),
+            Point3::new(-0.6, 1.0, 0.5),
+        ],
+        colors: vec![0xFF000080, 0x00FF0080, 0x0000FF80, 0xFFFF0040, 0x00FFFFC0],
+        offset: 0.2,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .instances(uniforms.colors.len())
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+    color_data(&framebuffer)
+}
+
+#[test]
+fn blended_output_is_identical_across_renders() {
+    let reference = render_blended_scene();
+    for _ in 0..100 {
+        assert!(render_blended_scene() == reference);
+    }
+}
+
+#[test]
+fn dedicated_pool_matches_the_global_pool() {
+    let pool = ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    let pooled = render_blended_scene_with(Rasterizer::with_thread_pool(pool));
+
+    assert!(pooled == render_blended_scene());
+}
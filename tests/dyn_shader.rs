@@ -0,0 +1,89 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::{Point2, Point3};
+
+use rast::graphics::*;
+
+use common::*;
+
+// colors each vertex by its index, so any difference in interpolation shows up in the output
+struct GradientShader {}
+
+impl Shader for GradientShader {
+    type Uniform = Vec<Point3<f32>>;
+    type Working = Point3<f32>;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let mut color = Point3::origin();
+        color[context.vertex_id % 3] = 1.0;
+
+        VertexOutput {
+            position: context.data[context.vertex_id],
+            data: color,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        let color = context.working;
+        pack_channels([color.x, color.y, color.z, 1.0])
+    }
+
+    fn point_corner(&self, _working: Self::Working, uv: Point2<f32>) -> Self::Working {
+        Point3::new(uv.x, uv.y, 0.5)
+    }
+}
+
+fn render(dynamic: bool, topology: Topology) -> Vec<u32> {
+    let framebuffer = Arc::new(Mutex::new(cleared_framebuffer(32, 32, false)));
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(framebuffer.clone()).unwrap();
+
+    let vertices = vec![
+        Point3::new(-0.9, -0.8, 0.5),
+        Point3::new(0.7, -0.2, 1.5),
+        Point3::new(-0.3, 0.9, 0.8),
+    ];
+
+    if dynamic {
+        let mut pipeline: DynPipeline =
+            Pipeline::builder(Box::new(FlatShader {}) as Box<dyn DynShader>)
+                .topology(topology)
+                .point_size(6.0)
+                .build()
+                .unwrap();
+
+        // swapped out after the pipeline is built, like a reloaded shader would be
+        pipeline.shader = Box::new(GradientShader {});
+
+        let uniforms: DynUniform = Box::new(vertices);
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+        rast.render_indexed(&call).unwrap();
+    } else {
+        let pipeline = Pipeline::builder(GradientShader {})
+            .topology(topology)
+            .point_size(6.0)
+            .build()
+            .unwrap();
+
+        let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &vertices).build();
+        rast.render_indexed(&call).unwrap();
+    }
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn dyn_shader_matches_static_dispatch() {
+    for topology in [Topology::TriangleList, Topology::PointList] {
+        let expected = render(false, topology);
+        let drawn = expected
+            .iter()
+            .filter(|color| **color != CLEAR_COLOR)
+            .count();
+        assert!(drawn > 50, "{topology:?}");
+
+        assert!(render(true, topology) == expected, "{topology:?}");
+    }
+}
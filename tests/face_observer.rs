@@ -0,0 +1,68 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::{Point2, Point3};
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn observer_sees_every_face() {
+    let (mut rast, framebuffer) = target(8, 8, false);
+
+    let faces = Arc::new(Mutex::new(Vec::new()));
+    let observed = faces.clone();
+    rast.set_face_observer(Some(Box::new(move |info: &FaceDebugInfo| {
+        observed
+            .lock()
+            .unwrap()
+            .push((info.screen_points.to_vec(), info.culled));
+    })));
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .min_triangle_area(1.0)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            // covers the top left half of the framebuffer
+            Point3::new(-1.0, -1.0, 0.5),
+            Point3::new(1.0, -1.0, 0.5),
+            Point3::new(-1.0, 1.0, 0.5),
+            // entirely off screen
+            Point3::new(5.0, 5.0, 0.5),
+            Point3::new(6.0, 5.0, 0.5),
+            Point3::new(5.0, 6.0, 0.5),
+            // too small to keep
+            Point3::new(0.5, 0.5, 0.5),
+            Point3::new(0.55, 0.5, 0.5),
+            Point3::new(0.5, 0.55, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call =
+        IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 3, 4, 5, 6, 7, 8], &uniforms).build();
+
+    rast.render_indexed(&call).unwrap();
+
+    let faces = faces.lock().unwrap();
+    let culled: Vec<_> = faces.iter().map(|(_, culled)| *culled).collect();
+    assert_eq!(culled, [false, true, true]);
+
+    assert_eq!(
+        faces[0].0,
+        [
+            Point2::new(0.0, 0.0),
+            Point2::new(8.0, 0.0),
+            Point2::new(0.0, 8.0)
+        ]
+    );
+
+    // only the first face drew anything
+    assert_eq!(pixel(&framebuffer, 1, 1), 0xFFFFFFFF);
+    assert_eq!(pixel(&framebuffer, 6, 6), CLEAR_COLOR);
+}
@@ -0,0 +1,337 @@
+mod common;
+
+use std::sync::Mutex;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+// packs frag_coord into the output color: x and y of the pixel in the top two bytes, the
+// fractional part of x in tenths below that, and w * 100 in the low byte
+struct FragCoordShader {}
+
+impl Shader for FragCoordShader {
+    type Uniform = FlatUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        let coord = context.frag_coord;
+        ((coord.x as u32) << 24)
+            | ((coord.y as u32) << 16)
+            | (((coord.x.fract() * 10.0).round() as u32) << 8)
+            | (coord.w * 100.0).round() as u32
+    }
+}
+
+fn render_frag_coords(origin: Origin) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(8, 6, false);
+    rast.set_origin(origin);
+
+    let pipeline = Pipeline::builder(FragCoordShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn frag_coord_is_the_pixel_center_from_the_top_left() {
+    for origin in [Origin::TopLeft, Origin::BottomLeft] {
+        let data = render_frag_coords(origin);
+        for y in 0..6 {
+            for x in 0..8 {
+                // a depth of 0.5 gives w = 2
+                let expected = ((x << 24) | (y << 16) | (5 << 8) | 200) as u32;
+                assert_eq!(data[y * 8 + x], expected, "pixel ({x}, {y}), {origin:?}");
+            }
+        }
+    }
+}
+
+// writes frag_coord.z in hundredths to the output color
+struct WindowDepthShader {}
+
+impl Shader for WindowDepthShader {
+    type Uniform = FlatUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        (context.frag_coord.z * 100.0).round() as u32
+    }
+}
+
+fn render_window_depth(depth: f32, viewport: Viewport) -> u32 {
+    let (mut rast, framebuffer) = target(4, 4, false);
+
+    let pipeline = Pipeline::builder(WindowDepthShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(depth),
+        color: 0,
+    };
+
+    let viewports = [viewport];
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .viewports(&viewports)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+    pixel(&framebuffer, 1, 1)
+}
+
+#[test]
+fn frag_coord_depth_is_normalized_by_the_viewport() {
+    let ranged = |min_depth, max_depth| Viewport {
+        min_depth,
+        max_depth,
+        ..Viewport::full(4, 4)
+    };
+
+    assert_eq!(render_window_depth(0.5, ranged(0.0, 2.0)), 25);
+    assert_eq!(render_window_depth(1.5, ranged(1.0, 3.0)), 25);
+    assert_eq!(render_window_depth(3.0, ranged(1.0, 3.0)), 100);
+
+    // the default far plane is infinitely far away, where one unit past the near plane is halfway
+    assert_eq!(render_window_depth(1.0, Viewport::full(4, 4)), 50);
+    assert_eq!(render_window_depth(3.0, Viewport::full(4, 4)), 75);
+}
+
+// colors each vertex by its id, so the gradient follows the vertices whichever way they're wound
+struct GradientShader {}
+
+impl Shader for GradientShader {
+    type Uniform = Vec<Point3<f32>>;
+    type Working = Point3<f32>;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let mut color = Point3::origin();
+        color[context.vertex_id] = 1.0;
+
+        VertexOutput {
+            position: context.data[context.vertex_id],
+            data: color,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        let color = context.working;
+        pack_channels([color.x, color.y, color.z, 1.0])
+    }
+}
+
+// with culling off, the back face has to interpolate exactly like the front face. the triangle is
+// axis aligned, so plenty of pixel centers land right on its edges
+#[test]
+fn back_faces_interpolate_like_front_faces() {
+    let vertices = vec![
+        Point3::new(-1.0, -1.0, 0.5),
+        Point3::new(1.0, -1.0, 1.0),
+        Point3::new(-1.0, 1.0, 2.0),
+    ];
+
+    let render = |indices: &[u16]| {
+        let (mut rast, framebuffer) = target(16, 16, false);
+        let pipeline = Pipeline::builder(GradientShader {})
+            .cull_back(false)
+            .build()
+            .unwrap();
+
+        let call = IndexedRenderCall::builder(&pipeline, indices, &vertices).build();
+        rast.render_indexed(&call).unwrap();
+        color_data(&framebuffer)
+    };
+
+    let front = render(&[0, 1, 2]);
+    assert!(front.iter().any(|color| *color != CLEAR_COLOR));
+    assert!(render(&[0, 2, 1]) == front);
+}
+
+// flips the normal on back faces like a two-sided material, green if it ends up facing the
+// camera and red otherwise
+struct TwoSidedShader {}
+
+impl Shader for TwoSidedShader {
+    type Uniform = Vec<Point3<f32>>;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data[context.vertex_id],
+            data: 1.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        let normal = match context.front_facing {
+            true => context.working,
+            false => -context.working,
+        };
+
+        match normal > 0.0 {
+            true => 0x00FF00FF,
+            false => 0xFF0000FF,
+        }
+    }
+}
+
+// the quad is split into one triangle of each winding
+#[test]
+fn front_facing_tells_the_two_sides_apart() {
+    let (mut rast, framebuffer) = target(16, 16, false);
+    let pipeline = Pipeline::builder(TwoSidedShader {}).build().unwrap();
+    let vertices = vec![
+        Point3::new(-1.0, -1.0, 0.5),
+        Point3::new(1.0, -1.0, 0.5),
+        Point3::new(1.0, 1.0, 0.5),
+        Point3::new(-1.0, 1.0, 0.5),
+    ];
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 0, 3, 2], &vertices).build();
+    rast.render_indexed(&call).unwrap();
+
+    // front faces are clockwise by default, which the top right triangle is on screen
+    assert_eq!(pixel(&framebuffer, 12, 3), 0x00FF00FF);
+    assert_eq!(pixel(&framebuffer, 3, 12), 0xFF0000FF);
+
+    // neither side loses its edge along the diagonal
+    let colors = color_data(&framebuffer);
+    assert!(
+        colors
+            .iter()
+            .all(|color| [0x00FF00FF, 0xFF0000FF].contains(color))
+    );
+}
+
+// records the interpolated one-hot vertex ids of every fragment, which are its blend weights
+struct WeightShader {}
+
+struct WeightUniforms {
+    vertices: Vec<Point3<f32>>,
+    weights: Mutex<Vec<Point3<f32>>>,
+}
+
+impl Shader for WeightShader {
+    type Uniform = WeightUniforms;
+    type Working = Point3<f32>;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let mut weights = Point3::origin();
+        weights[context.vertex_id] = 1.0;
+
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: weights,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.weights.lock().unwrap().push(context.working);
+        0xFFFFFFFF
+    }
+}
+
+fn sliver_weights(clamp: bool) -> Vec<Point3<f32>> {
+    let (mut rast, _framebuffer) = target(64, 64, false);
+    let pipeline = Pipeline::builder(WeightShader {})
+        .clamp_barycentric(clamp)
+        .build()
+        .unwrap();
+
+    // long and thin, with depths far apart so perspective correction amplifies any error
+    let uniforms = WeightUniforms {
+        vertices: vec![
+            Point3::new(-0.99, -0.97, 0.01),
+            Point3::new(0.99, 0.93, 100.0),
+            Point3::new(0.98, 0.97, 0.5),
+        ],
+        weights: Mutex::new(Vec::new()),
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    uniforms.weights.into_inner().unwrap()
+}
+
+#[test]
+fn clamped_weights_stay_within_the_triangle() {
+    // clamping only changes the weights, not which pixels are covered
+    let clamped = sliver_weights(true);
+    assert!(!clamped.is_empty());
+    assert_eq!(clamped.len(), sliver_weights(false).len());
+
+    for weights in clamped {
+        assert!(
+            weights.iter().all(|weight| (0.0..=1.0).contains(weight)),
+            "{weights:?}"
+        );
+        assert!(
+            (weights.iter().sum::<f32>() - 1.0).abs() < 1e-5,
+            "{weights:?}"
+        );
+    }
+}
+
+// fills the framebuffer with a texture of the same size, one texel per pixel
+struct TextureShader {}
+
+impl Shader for TextureShader {
+    type Uniform = Image<u32>;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: fullscreen(0.5)[context.vertex_id],
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        let coord = context.frag_coord;
+        *context.data.at(coord.x as usize, coord.y as usize).unwrap()
+    }
+}
+
+#[test]
+fn color_keyed_texels_show_the_destination() {
+    let (mut rast, framebuffer) = target(2, 2, false);
+
+    // alpha is ignored when matching the key
+    let pipeline = Pipeline::builder(TextureShader {})
+        .color_key(0xFF00FF00)
+        .build()
+        .unwrap();
+
+    let mut texture = Image::new(2, 2);
+    texture
+        .data_mut()
+        .copy_from_slice(&[0xFF0000FF, 0xFF00FFFF, 0x00FF00FF, 0xFF00FF80]);
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &texture).build();
+    rast.render_indexed(&call).unwrap();
+
+    assert_eq!(
+        color_data(&framebuffer),
+        [0xFF0000FF, CLEAR_COLOR, 0x00FF00FF, CLEAR_COLOR]
+    );
+}
@@ -0,0 +1,427 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn scanline_iterator_visits_the_same_rows() {
+    let mut framebuffer = Framebuffer::new(5, 6, 2, true);
+
+    let summarize = |scanline: &MutableScanline| {
+        (
+            scanline.y,
+            scanline.color.len(),
+            scanline.color[0].len(),
+            scanline.depth.is_some(),
+        )
+    };
+
+    let collected: Vec<_> = framebuffer.scanlines(1, 4).iter().map(summarize).collect();
+    let iterated: Vec<_> = framebuffer
+        .scanlines_iter(1, 4)
+        .map(|scanline| summarize(&scanline))
+        .collect();
+
+    assert_eq!(collected, iterated);
+    assert_eq!(iterated.first().map(|row| row.0), Some(1));
+    assert_eq!(iterated.len(), 4);
+
+    // rows handed out by the iterator alias the framebuffer
+    for mut scanline in framebuffer.scanlines_iter(0, 6) {
+        scanline.color[1][2] = scanline.y as u32;
+    }
+
+    assert_eq!(*framebuffer.color_attachments()[1].at(2, 5).unwrap(), 5);
+}
+
+#[test]
+fn scanlines_hold_one_row_per_attachment() {
+    for attachments in 0..3 {
+        let mut framebuffer = Framebuffer::new(4, 3, attachments, true);
+        for mut scanline in framebuffer.scanlines_iter(0, 3) {
+            assert_eq!(scanline.color.len(), attachments);
+            for row in scanline.color.iter_mut() {
+                row[3] = scanline.y as u32 + 1;
+            }
+        }
+
+        for attachment in framebuffer.color_attachments() {
+            assert_eq!(*attachment.at(3, 2).unwrap(), 3);
+        }
+    }
+}
+
+#[test]
+fn non_finite_clear_depth_is_rejected() {
+    let mut framebuffer = Framebuffer::new(2, 2, 1, true);
+
+    let nan = ClearValue {
+        color: 0,
+        depth: f32::NAN,
+    };
+
+    assert!(matches!(
+        framebuffer.clear(&nan),
+        Err(FramebufferError::InvalidClearDepth)
+    ));
+
+    let region = Scissor {
+        x: 0,
+        y: 0,
+        width: 1,
+        height: 1,
+    };
+
+    let infinite = ClearValue {
+        color: 0,
+        depth: f32::INFINITY,
+    };
+
+    assert!(matches!(
+        framebuffer.clear_region(&infinite, &region),
+        Err(FramebufferError::InvalidClearDepth)
+    ));
+
+    framebuffer
+        .clear(&ClearValue {
+            color: 0,
+            depth: 1.0,
+        })
+        .unwrap();
+
+    let depth = framebuffer.depth_attachment().as_ref().unwrap();
+    assert_eq!(depth.at(1, 1).unwrap(), 1.0);
+
+    // the rasterizer checks the value before locking the render target
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(Arc::new(Mutex::new(framebuffer)))
+        .unwrap();
+
+    assert!(matches!(
+        rast.clear(&nan),
+        Err(RasterizerError::InvalidClearDepth)
+    ));
+}
+
+#[test]
+fn memory_bytes_counts_every_attachment() {
+    // two color attachments and depth, 4 bytes per pixel each
+    let mut framebuffer = Framebuffer::new(10, 20, 2, true);
+    assert_eq!(framebuffer.memory_bytes(), 10 * 20 * 4 * 3);
+
+    framebuffer.set_overdraw_tracking(true);
+    assert_eq!(framebuffer.memory_bytes(), 10 * 20 * 4 * 4);
+
+    assert_eq!(Framebuffer::new(3, 3, 1, false).memory_bytes(), 3 * 3 * 4);
+}
+
+#[test]
+fn depth_attachment_round_trips() {
+    let mut depth = Image::new(3, 2);
+    depth.exchange(2, 1, 0.75);
+
+    let mut framebuffer =
+        Framebuffer::with_attachments(vec![Image::new(3, 2)], Some(depth)).unwrap();
+    assert_eq!(framebuffer.size(), (3, 2));
+
+    let depth = framebuffer.take_depth().unwrap();
+    assert!(framebuffer.depth_attachment().is_none());
+    assert_eq!(*depth.at(2, 1).unwrap(), 0.75);
+
+    // attachments of another size are rejected and leave the framebuffer as it was
+    assert!(matches!(
+        framebuffer.set_depth(Some(Image::new(2, 2))),
+        Err(FramebufferError::AttachmentSizeMismatch)
+    ));
+
+    assert!(framebuffer.depth_attachment().is_none());
+
+    framebuffer.set_depth(Some(depth)).unwrap();
+    let depth = framebuffer.depth_attachment().as_ref().unwrap();
+    assert_eq!(depth.at(2, 1).unwrap(), 0.75);
+}
+
+#[test]
+fn attachments_must_agree_on_size() {
+    assert!(matches!(
+        Framebuffer::with_attachments(Vec::new(), None),
+        Err(FramebufferError::NoAttachments)
+    ));
+
+    assert!(matches!(
+        Framebuffer::with_attachments(vec![Image::new(3, 2)], Some(Image::new(2, 3))),
+        Err(FramebufferError::AttachmentSizeMismatch)
+    ));
+}
+
+#[test]
+fn resize_preserves_the_overlap_and_clears_the_rest() {
+    let mut framebuffer = Framebuffer::new(4, 4, 1, true);
+    framebuffer
+        .clear(&ClearValue {
+            color: 0x11223344,
+            depth: 0.5,
+        })
+        .unwrap();
+
+    framebuffer.resize(8, 8, true);
+    assert_eq!(framebuffer.size(), (8, 8));
+
+    let color = &framebuffer.color_attachments()[0];
+    let depth = framebuffer.depth_attachment().as_ref().unwrap();
+    for (x, y) in color.coordinates() {
+        let preserved = x < 4 && y < 4;
+
+        let expected_color = if preserved { 0x11223344 } else { 0 };
+        assert_eq!(
+            *color.at(x, y).unwrap(),
+            expected_color,
+            "color at ({x}, {y})"
+        );
+
+        let expected_depth = if preserved { 0.5 } else { 0.0 };
+        assert_eq!(
+            depth.at(x, y).unwrap(),
+            expected_depth,
+            "depth at ({x}, {y})"
+        );
+    }
+
+    // without preserving, everything starts over
+    framebuffer.resize(2, 2, false);
+    assert_eq!(framebuffer.size(), (2, 2));
+    assert!(
+        framebuffer.color_attachments()[0]
+            .data()
+            .iter()
+            .all(|color| *color == 0)
+    );
+}
+
+// a rectangle between two corners in NDC, as two triangles
+fn rectangle(min: (f32, f32), max: (f32, f32)) -> Vec<Point3<f32>> {
+    let corner = |x, y| Point3::new(x, y, 0.5);
+    vec![
+        corner(min.0, min.1),
+        corner(max.0, min.1),
+        corner(min.0, max.1),
+        corner(max.0, min.1),
+        corner(max.0, max.1),
+        corner(min.0, max.1),
+    ]
+}
+
+#[test]
+fn overdraw_counts_overlapping_fragments() {
+    let (mut rast, framebuffer) = target(8, 8, false);
+    framebuffer.lock().unwrap().set_overdraw_tracking(true);
+
+    // the left half and the top half, overlapping in the top left quadrant
+    let mut vertices = rectangle((-1.0, -1.0), (0.0, 1.0));
+    vertices.extend(rectangle((-1.0, -1.0), (1.0, 0.0)));
+
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices,
+        color: 0xFFFFFFFF,
+    };
+
+    let indices: Vec<_> = (0..12).collect();
+    let call = IndexedRenderCall::builder(&pipeline, &indices, &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    let framebuffer = framebuffer.lock().unwrap();
+    let overdraw = framebuffer.overdraw().as_ref().unwrap();
+    for (x, y) in overdraw.coordinates() {
+        let expected = (x < 4) as u32 + (y < 4) as u32;
+        assert_eq!(*overdraw.at(x, y).unwrap(), expected, "pixel ({x}, {y})");
+    }
+}
+
+#[test]
+fn info_reports_the_attachments() {
+    assert_eq!(
+        Framebuffer::new(3, 4, 2, true).info(),
+        FramebufferInfo {
+            width: 3,
+            height: 4,
+            color_attachments: 2,
+            color_format: PixelFormat::Rgba8,
+            has_depth: true,
+            depth_format: DepthFormat::F32,
+            sample_count: 1,
+        }
+    );
+
+    let depth_only = Framebuffer::new(5, 2, 0, true).info();
+    assert_eq!(depth_only.color_attachments, 0);
+    assert!(depth_only.has_depth);
+
+    let color_only = Framebuffer::new(5, 2, 1, false).info();
+    assert_eq!((color_only.width, color_only.height), (5, 2));
+    assert!(!color_only.has_depth);
+}
+
+#[test]
+fn double_buffer_swap_exchanges_contents() {
+    let mut buffers = DoubleBuffer::new(2, 2, 1, false);
+    buffers
+        .back()
+        .lock()
+        .unwrap()
+        .clear(&ClearValue {
+            color: 0xFF0000FF,
+            depth: 1.0,
+        })
+        .unwrap();
+
+    let front_color = |buffers: &DoubleBuffer| color_data(buffers.front());
+    let back_color = |buffers: &DoubleBuffer| color_data(buffers.back());
+
+    assert_eq!(front_color(&buffers), [0; 4]);
+    assert_eq!(back_color(&buffers), [0xFF0000FF; 4]);
+
+    buffers.swap();
+    assert_eq!(front_color(&buffers), [0xFF0000FF; 4]);
+    assert_eq!(back_color(&buffers), [0; 4]);
+
+    buffers.swap();
+    assert_eq!(front_color(&buffers), [0; 4]);
+}
+
+#[test]
+#[should_panic(expected = "must be nonzero")]
+fn zero_sized_framebuffer_panics() {
+    Framebuffer::new(0, 4, 1, true);
+}
+
+#[test]
+fn zero_sized_descriptors_are_rejected() {
+    for (width, height) in [(0, 4), (4, 0), (0, 0)] {
+        assert!(matches!(
+            Framebuffer::from_desc(&FramebufferDesc::new(width, height)),
+            Err(FramebufferError::ZeroSize)
+        ));
+    }
+
+    assert!(matches!(
+        Framebuffer::with_attachments(vec![Image::new(0, 0)], None),
+        Err(FramebufferError::ZeroSize)
+    ));
+}
+
+#[test]
+fn color_attachments_are_indexed() {
+    let mut framebuffer = Framebuffer::new(2, 2, 2, false);
+    framebuffer
+        .color_attachment_mut(1)
+        .unwrap()
+        .exchange(1, 1, 0x123456FF);
+
+    assert_eq!(
+        *framebuffer.color_attachment(1).unwrap().at(1, 1).unwrap(),
+        0x123456FF
+    );
+
+    // the other attachment is untouched
+    assert_eq!(
+        *framebuffer.color_attachment(0).unwrap().at(1, 1).unwrap(),
+        0
+    );
+
+    assert!(framebuffer.color_attachment(2).is_none());
+    assert!(framebuffer.color_attachment_mut(2).is_none());
+}
+
+#[test]
+fn info_reflects_the_descriptor() {
+    let desc = FramebufferDesc {
+        color_attachments: 3,
+        depth: true,
+        depth_format: DepthFormat::Unorm24 {
+            near: 0.5,
+            far: 10.0,
+        },
+        ..FramebufferDesc::new(7, 5)
+    };
+
+    assert_eq!(
+        Framebuffer::from_desc(&desc).unwrap().info(),
+        FramebufferInfo {
+            width: 7,
+            height: 5,
+            color_attachments: 3,
+            color_format: PixelFormat::Rgba8,
+            has_depth: true,
+            depth_format: DepthFormat::Unorm24 {
+                near: 0.5,
+                far: 10.0,
+            },
+            sample_count: 1,
+        }
+    );
+
+    // the descriptor's defaults are a single color attachment without depth
+    let info = Framebuffer::from_desc(&FramebufferDesc::new(2, 9))
+        .unwrap()
+        .info();
+
+    assert_eq!((info.width, info.height), (2, 9));
+    assert_eq!(info.color_attachments, 1);
+    assert!(!info.has_depth);
+}
+
+// inverts the colors of one attachment inside a scissor that hangs off the bottom right corner
+#[test]
+fn for_each_pixel_in_only_visits_the_region() {
+    let mut framebuffer = Framebuffer::new(8, 6, 2, false);
+    framebuffer
+        .clear(&ClearValue {
+            color: 0x336699FF,
+            depth: 1.0,
+        })
+        .unwrap();
+
+    let region = Scissor {
+        x: 5,
+        y: 4,
+        width: 10,
+        height: 10,
+    };
+
+    let mut visited = Vec::new();
+    framebuffer
+        .for_each_pixel_in(1, &region, |x, y, color| {
+            visited.push((x, y));
+            *color = !*color | 0xFF;
+        })
+        .unwrap();
+
+    // clipped to the framebuffer, row by row
+    let expected = Vec::from_iter((4..6).flat_map(|y| (5..8).map(move |x| (x, y))));
+    assert_eq!(visited, expected);
+
+    let inverted = framebuffer.color_attachment(1).unwrap();
+    for (x, y) in inverted.coordinates() {
+        let color = match x >= 5 && y >= 4 {
+            true => 0xCC9966FF,
+            false => 0x336699FF,
+        };
+
+        assert_eq!(*inverted.at(x, y).unwrap(), color, "pixel ({x}, {y})");
+    }
+
+    // the other attachment is left alone
+    let untouched = framebuffer.color_attachment(0).unwrap();
+    assert!(untouched.data().iter().all(|color| *color == 0x336699FF));
+
+    assert!(matches!(
+        framebuffer.for_each_pixel_in(2, &region, |_, _, _| {}),
+        Err(FramebufferError::NoSuchAttachment)
+    ));
+}
@@ -0,0 +1,69 @@
+use nalgebra::{Point2, Point3, Vector3};
+
+use rast::graphics::*;
+
+#[test]
+fn front_facing_follows_the_winding_order() {
+    // y points down on screen, so this goes clockwise
+    let clockwise = [
+        Point2::new(0.0, 0.0),
+        Point2::new(1.0, 0.0),
+        Point2::new(0.0, 1.0),
+    ];
+
+    let [a, b, c] = clockwise;
+    let counterclockwise = [a, c, b];
+
+    assert!(is_front_facing(clockwise, WindingOrder::Clockwise));
+    assert!(!is_front_facing(clockwise, WindingOrder::CounterClockwise));
+    assert!(is_front_facing(
+        counterclockwise,
+        WindingOrder::CounterClockwise
+    ));
+    assert!(!is_front_facing(counterclockwise, WindingOrder::Clockwise));
+
+    // degenerate triangles face neither way
+    let line = [a, b, Point2::new(2.0, 0.0)];
+    assert!(!is_front_facing(line, WindingOrder::Clockwise));
+    assert!(!is_front_facing(line, WindingOrder::CounterClockwise));
+}
+
+// quads x quads unit squares on z = 0, wound so every cross product points along +z
+fn subdivided_plane(quads: usize) -> (Vec<Point3<f32>>, Vec<u16>) {
+    let side = quads + 1;
+    let positions = Vec::from_iter(
+        (0..side * side).map(|i| Point3::new((i % side) as f32, (i / side) as f32, 0.0)),
+    );
+
+    let mut indices = Vec::new();
+    for y in 0..quads {
+        for x in 0..quads {
+            let corner = |dx: usize, dy: usize| ((y + dy) * side + x + dx) as u16;
+            indices.extend([corner(0, 0), corner(1, 0), corner(0, 1)]);
+            indices.extend([corner(1, 0), corner(1, 1), corner(0, 1)]);
+        }
+    }
+
+    (positions, indices)
+}
+
+#[test]
+fn flat_planes_have_flat_smooth_normals() {
+    let (mut positions, mut indices) = subdivided_plane(4);
+
+    // a degenerate face sharing plane vertices doesn't tilt them, and a vertex only it uses gets
+    // no normal at all
+    let lonely = positions.len() as u16;
+    positions.push(Point3::new(2.0, 2.0, 5.0));
+    indices.extend([0, 0, lonely]);
+
+    let normals = compute_smooth_normals(&positions, &indices);
+    assert_eq!(normals.len(), positions.len());
+
+    let (lonely, plane) = normals.split_last().unwrap();
+    for normal in plane {
+        assert!((normal - Vector3::z()).norm() < 1e-6, "{normal}");
+    }
+
+    assert_eq!(*lonely, Vector3::zeros());
+}
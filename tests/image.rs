@@ -0,0 +1,106 @@
+use rast::graphics::*;
+
+#[test]
+fn luminance_histogram_buckets_black_and_white() {
+    let mut image = Image::new(4, 2);
+    for (i, pixel) in image.data_mut().iter_mut().enumerate() {
+        *pixel = match i % 4 < 2 {
+            true => 0x000000FF,
+            false => 0xFFFFFFFF,
+        };
+    }
+
+    assert_eq!(image.luminance_histogram(8), vec![4, 0, 0, 0, 0, 0, 0, 4]);
+    assert!(image.luminance_histogram(0).is_empty());
+}
+
+// pixel values are their index, so every row holds a run of consecutive numbers
+fn numbered(width: usize, height: usize) -> Image<u32> {
+    let mut image = Image::new(width, height);
+    for (i, pixel) in image.data_mut().iter_mut().enumerate() {
+        *pixel = i as u32;
+    }
+
+    image
+}
+
+#[test]
+fn rows_are_slices_of_the_image() {
+    let mut image = numbered(3, 2);
+
+    assert_eq!(image.row(0), Some(&[0, 1, 2][..]));
+    assert_eq!(image.row(1), Some(&[3, 4, 5][..]));
+    assert_eq!(image.row(2), None);
+
+    image.row_mut(1).unwrap()[2] = 9;
+    assert_eq!(*image.at(2, 1).unwrap(), 9);
+    assert!(image.row_mut(2).is_none());
+}
+
+#[cfg(feature = "image-crate")]
+#[test]
+fn rgba_images_round_trip() {
+    let mut image = numbered(3, 2);
+    image.exchange(1, 1, 0x11223344);
+
+    let converted = image.to_rgba_image();
+    assert_eq!(converted.dimensions(), (3, 2));
+    assert_eq!(converted.get_pixel(1, 1).0, [0x11, 0x22, 0x33, 0x44]);
+
+    let restored = Image::from_rgba_image(&converted);
+    assert_eq!(restored.size(), (3, 2));
+    assert_eq!(restored.data(), image.data());
+}
+
+#[test]
+fn crop_copies_the_region() {
+    let image = numbered(4, 4);
+
+    let cropped = image
+        .crop(&Scissor {
+            x: 1,
+            y: 1,
+            width: 2,
+            height: 2,
+        })
+        .unwrap();
+
+    assert_eq!(cropped.size(), (2, 2));
+    assert_eq!(cropped.data(), &[5, 6, 9, 10]);
+
+    // regions hanging off the right or bottom edge don't crop at all
+    let out_of_bounds = [(3, 1, 2, 2), (0, 3, 1, 2), (4, 0, 1, 1)];
+    for (x, y, width, height) in out_of_bounds {
+        let scissor = Scissor {
+            x,
+            y,
+            width,
+            height,
+        };
+
+        assert!(image.crop(&scissor).is_none(), "{scissor:?}");
+    }
+}
+
+// changed_region as (x, y, width, height)
+fn changed(current: &Image<u32>, previous: &Image<u32>) -> Option<(usize, usize, usize, usize)> {
+    current
+        .changed_region(previous)
+        .map(|region| (region.x, region.y, region.width, region.height))
+}
+
+#[test]
+fn changed_region_bounds_the_differences() {
+    let previous = numbered(5, 4);
+    let mut current = numbered(5, 4);
+    assert_eq!(changed(&current, &previous), None);
+
+    current.exchange(3, 2, 100);
+    assert_eq!(changed(&current, &previous), Some((3, 2, 1, 1)));
+
+    current.exchange(1, 0, 100);
+    assert_eq!(changed(&current, &previous), Some((1, 0, 3, 3)));
+
+    // a different size counts as a change everywhere
+    assert_eq!(changed(&numbered(2, 2), &previous), Some((0, 0, 2, 2)));
+}
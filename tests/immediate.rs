@@ -0,0 +1,61 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn draw_triangle_fills_its_interior() {
+    let mut framebuffer = cleared_framebuffer(16, 16, true);
+    let mut rast = Rasterizer::new();
+
+    // in pixels, with the centroid at (6, 4)
+    let vertices = [
+        Point3::new(2.0, 2.0, 0.5),
+        Point3::new(14.0, 2.0, 0.5),
+        Point3::new(2.0, 8.0, 0.5),
+    ];
+
+    rast.draw_triangle(&mut framebuffer, vertices, 0xFF0000FF)
+        .unwrap();
+
+    let color = framebuffer.color_attachment(0).unwrap();
+    assert_eq!(*color.at(6, 4).unwrap(), 0xFF0000FF);
+
+    // past the hypotenuse and outside of the bounds entirely
+    assert_eq!(*color.at(12, 7).unwrap(), CLEAR_COLOR);
+    assert_eq!(*color.at(1, 1).unwrap(), CLEAR_COLOR);
+
+    let depth = framebuffer.depth_attachment().as_ref().unwrap();
+    assert_eq!(depth.at(6, 4).unwrap(), 0.5);
+}
+
+// z is w as well, so a vertex at or behind zero can't be drawn
+#[test]
+fn draw_triangle_rejects_non_positive_depths() {
+    let mut framebuffer = cleared_framebuffer(16, 16, false);
+    let mut rast = Rasterizer::new();
+
+    for z in [0.0, -1.0, f32::INFINITY] {
+        let vertices = [
+            Point3::new(2.0, 2.0, 0.5),
+            Point3::new(14.0, 2.0, z),
+            Point3::new(2.0, 8.0, 0.5),
+        ];
+
+        let result = rast.draw_triangle(&mut framebuffer, vertices, 0xFF0000FF);
+        assert!(
+            matches!(result, Err(RasterizerError::InvalidVertexDepth(depth)) if depth == z),
+            "{z}"
+        );
+    }
+
+    assert!(
+        framebuffer.color_attachments()[0]
+            .data()
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+}
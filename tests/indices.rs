@@ -0,0 +1,218 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+fn triangle() -> FlatUniforms {
+    FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    }
+}
+
+#[test]
+fn out_of_range_index_is_an_error() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = triangle();
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 5], &uniforms)
+        .vertex_count(3)
+        .build();
+
+    assert!(matches!(
+        rast.render_indexed(&call),
+        Err(RasterizerError::IndexOutOfRange { index: 5, max: 3 })
+    ));
+
+    assert!(matches!(
+        rast.render_indexed_tiled(&call, 2),
+        Err(RasterizerError::IndexOutOfRange { index: 5, max: 3 })
+    ));
+
+    // rejected before anything is drawn
+    assert_eq!(rast.stats().calls, 0);
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+}
+
+#[test]
+fn restart_index_is_not_validated() {
+    let (mut rast, _framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = triangle();
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 0xFFFF], &uniforms)
+        .vertex_count(3)
+        .primitive_restart(0xFFFF)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+}
+
+// without a vertex count there is nothing to check the indices against
+#[test]
+#[should_panic]
+fn indices_without_a_vertex_count_are_not_validated() {
+    let (mut rast, _framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = triangle();
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 5], &uniforms).build();
+    let _ = rast.render_indexed(&call);
+}
+
+#[test]
+#[should_panic]
+fn trusted_indices_are_not_validated() {
+    let (mut rast, _framebuffer) = target(4, 4, false);
+    rast.set_validate_indices(false);
+
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = triangle();
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 5], &uniforms)
+        .vertex_count(3)
+        .build();
+
+    let _ = rast.render_indexed(&call);
+}
+
+// zero instances or no indices draw nothing, but still count as a call
+#[test]
+fn empty_calls_are_no_ops() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = triangle();
+
+    let no_instances = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .instances(0)
+        .build();
+
+    rast.render_indexed(&no_instances).unwrap();
+    rast.render_indexed(&IndexedRenderCall::builder(&pipeline, &[], &uniforms).build())
+        .unwrap();
+
+    assert_eq!(rast.stats().calls, 2);
+    assert_eq!(rast.stats().faces_processed, 0);
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+}
+
+#[test]
+fn trailing_indices_are_an_error() {
+    let (mut rast, _framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = triangle();
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 0], &uniforms).build();
+    assert!(matches!(
+        rast.render_indexed(&call),
+        Err(RasterizerError::IncompletePrimitive(1))
+    ));
+
+    // every restarted run has to be whole on its own
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 0xFFFF, 0, 1], &uniforms)
+        .primitive_restart(0xFFFF)
+        .build();
+
+    assert!(matches!(
+        rast.render_indexed_tiled(&call, 2),
+        Err(RasterizerError::IncompletePrimitive(2))
+    ));
+
+    assert_eq!(rast.stats().calls, 0);
+}
+
+// strips and fans have no incomplete primitives, too few indices just draw nothing
+#[test]
+fn short_strip_draws_nothing() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {})
+        .topology(Topology::TriangleStrip)
+        .build()
+        .unwrap();
+
+    let uniforms = triangle();
+    rast.render_indexed(&IndexedRenderCall::builder(&pipeline, &[0, 1], &uniforms).build())
+        .unwrap();
+
+    assert_eq!(rast.stats().calls, 1);
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+}
+
+#[test]
+fn restart_index_splits_strips() {
+    let (mut rast, framebuffer) = target(8, 8, false);
+
+    let faces = Arc::new(Mutex::new(Vec::new()));
+    let observed = faces.clone();
+    rast.set_face_observer(Some(Box::new(move |info: &FaceDebugInfo| {
+        observed.lock().unwrap().push(info.screen_points.to_vec());
+    })));
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .topology(Topology::TriangleStrip)
+        .build()
+        .unwrap();
+
+    // a strip down the left quarter and one down the right quarter
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.5),
+            Point3::new(-0.5, -1.0, 0.5),
+            Point3::new(-1.0, 1.0, 0.5),
+            Point3::new(-0.5, 1.0, 0.5),
+            Point3::new(0.5, -1.0, 0.5),
+            Point3::new(1.0, -1.0, 0.5),
+            Point3::new(0.5, 1.0, 0.5),
+            Point3::new(1.0, 1.0, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let indices = [0, 1, 2, 3, 0xFFFF, 4, 5, 6, 7];
+    let call = IndexedRenderCall::builder(&pipeline, &indices, &uniforms)
+        .primitive_restart(0xFFFF)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+
+    // two triangles per strip, and none bridging the gap between them
+    let faces = faces.lock().unwrap();
+    assert_eq!(faces.len(), 4);
+
+    for (i, face) in faces.iter().enumerate() {
+        let expected = if i < 2 { 0.0..=2.0 } else { 6.0..=8.0 };
+        assert!(
+            face.iter().all(|point| expected.contains(&point.x)),
+            "{face:?}"
+        );
+    }
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let expected = match x {
+                0 | 1 | 6 | 7 => 0xFFFFFFFF,
+                _ => CLEAR_COLOR,
+            };
+
+            assert_eq!(pixel(&framebuffer, x, y), expected, "pixel ({x}, {y})");
+        }
+    }
+}
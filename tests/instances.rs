@@ -0,0 +1,220 @@
+mod common;
+
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use rast::graphics::*;
+
+use common::*;
+
+// transforms every vertex by view_projection * models[instance_id], multiplied out in
+// prepare_instance when hoisted and per vertex otherwise
+struct TransformShader {
+    hoist: bool,
+}
+
+struct TransformUniforms {
+    vertices: Vec<Point3<f32>>,
+    view_projection: Matrix4<f32>,
+    models: Vec<Matrix4<f32>>,
+}
+
+impl TransformShader {
+    fn transform(data: &TransformUniforms, instance_id: usize) -> Matrix4<f32> {
+        data.view_projection * data.models[instance_id]
+    }
+}
+
+impl Shader for TransformShader {
+    type Uniform = TransformUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let transform = match context.instance.transform {
+            Some(transform) => transform,
+            None => {
+                assert!(
+                    !self.hoist,
+                    "Hoisted transform missing from the instance cache!"
+                );
+                Self::transform(context.data, context.instance_id)
+            }
+        };
+
+        let position = transform * context.data.vertices[context.vertex_id].to_homogeneous();
+        VertexOutput {
+            position: Point3::from_homogeneous(position).unwrap(),
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        0x102030FF | ((context.instance_id as u32 + 1) << 8)
+    }
+
+    fn prepare_instance(&self, data: &Self::Uniform, instance_id: usize) -> InstanceCache {
+        InstanceCache {
+            transform: self.hoist.then(|| Self::transform(data, instance_id)),
+        }
+    }
+}
+
+fn render_instances(hoist: bool, tile_height: Option<usize>) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(64, 64, true);
+    let pipeline = Pipeline::builder(TransformShader { hoist })
+        .depth(DepthMode::Write)
+        .build()
+        .unwrap();
+
+    let uniforms = TransformUniforms {
+        vertices: vec![
+            Point3::new(-0.5, -0.5, 0.5),
+            Point3::new(0.5, -0.5, 0.5),
+            Point3::new(-0.5, 0.5, 0.5),
+        ],
+        view_projection: Matrix4::new_scaling(0.7),
+        models: (0..4)
+            .map(|i| {
+                let offset = Vector3::new(i as f32 * 0.2 - 0.3, i as f32 * 0.1, 0.0);
+                Matrix4::new_translation(&offset)
+            })
+            .collect(),
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .instances(uniforms.models.len())
+        .build();
+
+    match tile_height {
+        Some(tile_height) => rast.render_indexed_tiled(&call, tile_height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn cached_instance_transform_matches_per_vertex() {
+    let per_vertex = render_instances(false, None);
+    assert!(per_vertex.iter().any(|color| *color != CLEAR_COLOR));
+
+    assert!(render_instances(true, None) == per_vertex);
+    assert!(render_instances(true, Some(7)) == per_vertex);
+}
+
+// a mirrored model reverses the winding of its triangles, so back face culling drops them unless
+// the call flips the winding back
+fn render_mirrored(flip_winding: bool) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(16, 16, false);
+    let pipeline = Pipeline::builder(TransformShader { hoist: false })
+        .cull_back(true)
+        .build()
+        .unwrap();
+
+    let mirror = Matrix4::new_nonuniform_scaling(&Vector3::new(-1.0, 1.0, 1.0));
+    assert!(flips_winding(&mirror));
+
+    let uniforms = TransformUniforms {
+        vertices: fullscreen(0.5),
+        view_projection: Matrix4::identity(),
+        models: vec![mirror],
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .flip_winding(flip_winding)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+    color_data(&framebuffer)
+}
+
+#[test]
+fn flipped_winding_draws_mirrored_instances() {
+    assert!(
+        render_mirrored(false)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+    assert!(
+        render_mirrored(true)
+            .iter()
+            .all(|color| *color == 0x102031FF)
+    );
+}
+
+// the same transform as TransformShader, but with bounds so off screen instances are culled
+struct BoundedShader {}
+
+impl Shader for BoundedShader {
+    type Uniform = TransformUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        let transform = TransformShader::transform(context.data, context.instance_id);
+        let position = transform * context.data.vertices[context.vertex_id].to_homogeneous();
+
+        VertexOutput {
+            position: Point3::from_homogeneous(position).unwrap(),
+            data: 0.0,
+        }
+    }
+
+    fn fragment_stage(&self, _context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        0xFFFFFFFF
+    }
+
+    fn instance_bounds(&self, instance_id: usize, data: &Self::Uniform) -> Option<InstanceBounds> {
+        Some(InstanceBounds {
+            min: Point3::new(-0.5, -0.5, 0.5),
+            max: Point3::new(0.5, 0.5, 0.5),
+            transform: TransformShader::transform(data, instance_id),
+        })
+    }
+}
+
+#[test]
+fn off_screen_instances_process_no_faces() {
+    let (mut rast, framebuffer) = target(16, 16, false);
+    let pipeline = Pipeline::builder(BoundedShader {}).build().unwrap();
+
+    let uniforms = TransformUniforms {
+        vertices: vec![
+            Point3::new(-0.5, -0.5, 0.5),
+            Point3::new(0.5, -0.5, 0.5),
+            Point3::new(-0.5, 0.5, 0.5),
+        ],
+        view_projection: Matrix4::identity(),
+        models: vec![
+            Matrix4::identity(),
+            Matrix4::new_translation(&Vector3::new(10.0, 0.0, 0.0)),
+        ],
+    };
+
+    // only the instance far off the right edge
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .first_instance(1)
+        .instances(1)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+
+    let stats = rast.stats();
+    assert_eq!(stats.instances_culled, 1);
+    assert_eq!(stats.faces_processed, 0);
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+
+    // the on screen instance still draws
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .instances(2)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+
+    let stats = rast.stats();
+    assert_eq!(stats.instances_culled, 2);
+    assert_eq!(stats.faces_processed, 1);
+    assert_eq!(pixel(&framebuffer, 5, 5), 0xFFFFFFFF);
+}
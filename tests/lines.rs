@@ -0,0 +1,166 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 32;
+
+// a point given in framebuffer pixels
+fn pixel_to_ndc(x: f32, y: f32) -> Point3<f32> {
+    Point3::new(
+        x / WIDTH as f32 * 2.0 - 1.0,
+        y / HEIGHT as f32 * 2.0 - 1.0,
+        0.5,
+    )
+}
+
+fn draw_line(mode: LineRasterization, clear_depth: f32) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT, 1, true);
+    framebuffer
+        .clear(&ClearValue {
+            color: 0,
+            depth: clear_depth,
+        })
+        .unwrap();
+
+    let pipeline = Pipeline::builder(FlatShader {})
+        .topology(Topology::LineList)
+        .depth(DepthMode::Write)
+        .line_rasterization(mode)
+        .build()
+        .unwrap();
+
+    // a shallow slope, rising 10 pixels over 58
+    let uniforms = FlatUniforms {
+        vertices: vec![pixel_to_ndc(2.0, 10.5), pixel_to_ndc(60.0, 20.5)],
+        color: 0xFFFFFFFF,
+    };
+
+    let mut rast = Rasterizer::new();
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1], &uniforms).build();
+    rast.render_indexed_into(&call, &mut framebuffer).unwrap();
+
+    framebuffer
+}
+
+// alpha of every pixel in a column
+fn column_alpha(framebuffer: &Framebuffer, x: usize) -> Vec<u32> {
+    let color = &framebuffer.color_attachments()[0];
+    (0..HEIGHT)
+        .map(|y| *color.at(x, y).unwrap() & 0xFF)
+        .collect()
+}
+
+#[test]
+fn antialiased_line_splits_coverage_between_rows() {
+    let framebuffer = draw_line(LineRasterization::Antialiased, 1.0);
+    let depth = framebuffer.depth_attachment().as_ref().unwrap();
+
+    let mut partial_columns = 0;
+    for x in 0..WIDTH {
+        let alpha = column_alpha(&framebuffer, x);
+        let total: u32 = alpha.iter().sum();
+
+        if !(2..60).contains(&x) {
+            assert_eq!(total, 0, "column {x}");
+            continue;
+        }
+
+        // the line's coverage is shared by at most two pixels and adds up to one
+        let lit: Vec<_> = (0..HEIGHT).filter(|y| alpha[*y] > 0).collect();
+        assert!(total.abs_diff(255) <= 1, "column {x} totals {total}");
+        assert!(lit.len() <= 2, "column {x}");
+
+        if lit.len() == 2 {
+            partial_columns += 1;
+        }
+
+        assert_eq!(depth.at(x, lit[0]).unwrap(), 0.5);
+    }
+
+    assert!(
+        partial_columns > 40,
+        "only {partial_columns} partial columns"
+    );
+}
+
+#[test]
+fn antialiased_line_is_depth_tested() {
+    let framebuffer = draw_line(LineRasterization::Antialiased, 0.25);
+    let color = &framebuffer.color_attachments()[0];
+
+    assert!(color.data().iter().all(|pixel| *pixel == 0));
+}
+
+#[test]
+fn aliased_line_has_no_partial_pixels() {
+    let framebuffer = draw_line(LineRasterization::Aliased, 1.0);
+    let color = &framebuffer.color_attachments()[0];
+
+    assert!(color.data().contains(&0xFFFFFFFF));
+    assert!(
+        color
+            .data()
+            .iter()
+            .all(|pixel| *pixel == 0 || *pixel == 0xFFFFFFFF)
+    );
+}
+
+// outlines a square through the pixel centers from (4, 4) to (20, 20), returning the covered pixels
+// and how many segments were drawn
+fn draw_outline(topology: Topology) -> (Vec<(usize, usize)>, usize) {
+    let (mut rast, framebuffer) = target(WIDTH, HEIGHT, false);
+    let pipeline = Pipeline::builder(FlatShader {})
+        .topology(topology)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            pixel_to_ndc(4.5, 4.5),
+            pixel_to_ndc(20.5, 4.5),
+            pixel_to_ndc(20.5, 20.5),
+            pixel_to_ndc(4.5, 20.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 3], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    let framebuffer = framebuffer.lock().unwrap();
+    let color = &framebuffer.color_attachments()[0];
+    let covered = color
+        .coordinates()
+        .filter(|(x, y)| *color.at(*x, *y).unwrap() == 0xFFFFFFFF)
+        .collect();
+
+    (covered, rast.stats().faces_processed)
+}
+
+#[test]
+fn line_loops_close_the_strip() {
+    let (strip, strip_segments) = draw_outline(Topology::LineStrip);
+    let (line_loop, loop_segments) = draw_outline(Topology::LineLoop);
+
+    assert_eq!(strip_segments, 3);
+    assert_eq!(loop_segments, 4);
+
+    // the loop draws everything the strip does, plus the left edge back to the first vertex
+    assert!(strip.iter().all(|pixel| line_loop.contains(pixel)));
+    assert!(!strip.contains(&(4, 12)));
+    assert!(line_loop.contains(&(4, 12)));
+
+    let closing: Vec<_> = line_loop
+        .iter()
+        .filter(|pixel| !strip.contains(pixel))
+        .collect();
+
+    // every pixel between the last vertex and the first, right up to the one next to the start
+    assert_eq!(closing.len(), 15);
+    assert!(closing.iter().all(|(x, y)| *x == 4 && (5..20).contains(y)));
+}
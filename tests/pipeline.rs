@@ -0,0 +1,223 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn blending_must_match_the_attachment_count() {
+    let error = Pipeline::builder(FlatShader {})
+        .blending(vec![src_over(), src_over()])
+        .color_attachments(1)
+        .build()
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        PipelineError::BlendAttachmentCountMismatch {
+            expected: 1,
+            actual: 2
+        }
+    ));
+
+    assert_eq!(
+        error.to_string(),
+        "Pipeline blends 2 attachments but targets 1 color attachments!"
+    );
+}
+
+#[test]
+fn min_triangle_area_must_be_non_negative() {
+    for area in [-1.0, f32::NAN, f32::INFINITY] {
+        let error = Pipeline::builder(FlatShader {})
+            .min_triangle_area(area)
+            .build()
+            .unwrap_err();
+
+        assert!(matches!(error, PipelineError::InvalidMinTriangleArea(_)));
+        assert!(error.to_string().contains("non-negative"));
+    }
+
+    // zero is allowed and culls nothing
+    assert!(
+        Pipeline::builder(FlatShader {})
+            .min_triangle_area(0.0)
+            .build()
+            .is_ok()
+    );
+}
+
+#[test]
+fn min_triangle_area_is_rejected_on_lines() {
+    let error = Pipeline::builder(FlatShader {})
+        .topology(Topology::LineList)
+        .min_triangle_area(1.0)
+        .build()
+        .unwrap_err();
+
+    assert!(matches!(error, PipelineError::TriangleStateOnLines));
+}
+
+// draws a right triangle with legs of `size` pixels into a 32x32 framebuffer, returning how many
+// pixels it covered and how many faces were culled as too small
+fn draw_sized(size: f32, min_area: Option<f32>) -> (usize, usize) {
+    let (mut rast, framebuffer) = target(32, 32, false);
+
+    let mut builder = Pipeline::builder(FlatShader {});
+    if let Some(area) = min_area {
+        builder = builder.min_triangle_area(area);
+    }
+
+    let pipeline = builder.build().unwrap();
+
+    // one pixel is 1/16 of NDC across
+    let extent = size / 16.0;
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(0.0, 0.0, 0.5),
+            Point3::new(extent, 0.0, 0.5),
+            Point3::new(0.0, extent, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    let covered = color_data(&framebuffer)
+        .iter()
+        .filter(|color| **color == 0xFFFFFFFF)
+        .count();
+
+    (covered, rast.stats().faces_culled_small)
+}
+
+#[test]
+fn triangles_below_the_min_area_are_culled() {
+    // a 2px triangle draws something on its own...
+    let (covered, culled) = draw_sized(2.0, None);
+    assert!(covered > 0);
+    assert_eq!(culled, 0);
+
+    // ...but nothing once it falls under the threshold
+    assert_eq!(draw_sized(2.0, Some(8.0)), (0, 1));
+
+    // larger triangles are unaffected
+    let (covered, culled) = draw_sized(16.0, Some(8.0));
+    assert_eq!(covered, draw_sized(16.0, None).0);
+    assert!(covered > 0);
+    assert_eq!(culled, 0);
+}
+
+// a depth prepass with a depth only copy of the color pipeline. the prepass renders into a
+// framebuffer holding nothing but the depth image, so the colors are left alone and only the
+// depth test decides what the color pass draws
+#[test]
+fn depth_only_copies_write_depth_but_leave_color() {
+    let color = Pipeline::builder(FlatShader {})
+        .depth(DepthMode::Test)
+        .blending(vec![src_over()])
+        .build()
+        .unwrap();
+
+    let prepass = color.with_depth_only();
+    assert!(matches!(prepass.depth, DepthMode::Write));
+    assert!(prepass.blending.is_none());
+
+    // the copy is independent of the pipeline it came from
+    assert!(matches!(color.depth, DepthMode::Test));
+    assert!(color.blending.is_some());
+
+    let framebuffer = Arc::new(Mutex::new(cleared_framebuffer(8, 8, true)));
+    let depth = framebuffer.lock().unwrap().take_depth();
+    let depth_only = Arc::new(Mutex::new(
+        Framebuffer::with_attachments(Vec::new(), depth).unwrap(),
+    ));
+
+    let left_half = FlatUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.25),
+            Point3::new(0.0, -1.0, 0.25),
+            Point3::new(0.0, 1.0, 0.25),
+            Point3::new(-1.0, 1.0, 0.25),
+        ],
+        color: 0xFF0000FF,
+    };
+
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(depth_only.clone()).unwrap();
+    rast.render_indexed(
+        &IndexedRenderCall::builder(&prepass, &[0, 1, 2, 0, 2, 3], &left_half).build(),
+    )
+    .unwrap();
+    rast.pop_render_target().unwrap();
+
+    let depth = depth_only.lock().unwrap().take_depth().unwrap();
+    assert_eq!(*depth.at(0, 0).unwrap(), 0.25);
+    assert_eq!(*depth.at(7, 0).unwrap(), 1.0);
+
+    framebuffer.lock().unwrap().set_depth(Some(depth)).unwrap();
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+
+    // the color pass is hidden wherever the prepass drew
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0x00FF00FF,
+    };
+
+    rast.push_render_target(framebuffer.clone()).unwrap();
+    rast.render_indexed(&IndexedRenderCall::builder(&color, &[0, 1, 2], &uniforms).build())
+        .unwrap();
+
+    assert_eq!(pixel(&framebuffer, 0, 0), CLEAR_COLOR);
+    assert_eq!(pixel(&framebuffer, 7, 0), 0x00FF00FF);
+}
+
+// renders one slanted triangle at the given tessellation level, returning how many faces were
+// rendered and which pixels were covered
+fn draw_tessellated(level: u32) -> (usize, Vec<bool>) {
+    let (mut rast, framebuffer) = target(64, 64, false);
+    let pipeline = Pipeline::builder(FlatShader {})
+        .tessellation_level(level)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.9, -0.8, 0.5),
+            Point3::new(0.7, -0.2, 1.5),
+            Point3::new(-0.3, 0.9, 0.8),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    let covered = Vec::from_iter(
+        color_data(&framebuffer)
+            .iter()
+            .map(|color| *color == 0xFFFFFFFF),
+    );
+
+    (rast.stats().faces_rendered, covered)
+}
+
+#[test]
+fn tessellation_subdivides_without_changing_coverage() {
+    let (faces, covered) = draw_tessellated(1);
+    assert_eq!(faces, 1);
+    assert!(covered.contains(&true));
+
+    // level n splits every edge into n pieces, so level 2 renders 4 triangles over the same pixels
+    assert_eq!(draw_tessellated(2), (4, covered.clone()));
+    assert_eq!(draw_tessellated(3), (9, covered));
+}
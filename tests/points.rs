@@ -0,0 +1,83 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+// three particles centered on the pixels (4, 4), (12, 4) and (8, 12), returning the pixels they
+// lit
+fn draw_particles(
+    rast: &mut Rasterizer,
+    size: f32,
+) -> Result<Vec<(usize, usize)>, RasterizerError> {
+    let mut framebuffer = cleared_framebuffer(16, 16, false);
+    let pipeline = Pipeline::builder(FlatShader {})
+        .topology(Topology::PointList)
+        .point_size(size)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.4375, -0.4375, 0.5),
+            Point3::new(0.5625, -0.4375, 0.5),
+            Point3::new(0.0625, 0.5625, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed_into(&call, &mut framebuffer)?;
+
+    let color = &framebuffer.color_attachments()[0];
+    Ok(color
+        .coordinates()
+        .filter(|(x, y)| *color.at(*x, *y).unwrap() == 0xFFFFFFFF)
+        .collect())
+}
+
+// the pixels of a size x size square around each particle, for odd sizes
+fn squares(size: usize) -> Vec<(usize, usize)> {
+    let mut pixels = Vec::new();
+    for (x, y) in [(4, 4), (12, 4), (8, 12)] {
+        for py in y - size / 2..=y + size / 2 {
+            for px in x - size / 2..=x + size / 2 {
+                pixels.push((px, py));
+            }
+        }
+    }
+
+    pixels.sort_by_key(|(x, y)| (*y, *x));
+    pixels
+}
+
+#[test]
+fn particles_light_a_square_each() {
+    let mut rast = Rasterizer::new();
+    assert_eq!(draw_particles(&mut rast, 5.0).unwrap(), squares(5));
+}
+
+#[test]
+fn point_sizes_are_validated_and_clamped() {
+    let mut rast = Rasterizer::new();
+    rast.set_max_point_size(3.0);
+
+    assert!(draw_particles(&mut rast, 0.0).unwrap().is_empty());
+    assert_eq!(draw_particles(&mut rast, 1.0).unwrap(), squares(1));
+
+    // anything past the maximum draws at the maximum
+    assert_eq!(draw_particles(&mut rast, 100.0).unwrap(), squares(3));
+    assert_eq!(
+        draw_particles(&mut rast, f32::INFINITY).unwrap(),
+        squares(3)
+    );
+
+    for size in [-1.0, f32::NAN] {
+        assert!(matches!(
+            draw_particles(&mut rast, size),
+            Err(RasterizerError::InvalidPrimitiveSize)
+        ));
+    }
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn builder_defaults_draw_one_instance_everywhere() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    assert_eq!((call.vertex_offset, call.first_instance), (0, 0));
+    assert_eq!(call.instance_count, 1);
+    assert!(call.scissor.is_none() && call.render_area.is_none());
+    assert!(call.viewports.is_empty());
+
+    rast.render_indexed(&call).unwrap();
+
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == 0xFF0000FF)
+    );
+    assert_eq!(rast.stats().instances, 1);
+    assert_eq!(rast.stats().faces_rendered, 1);
+}
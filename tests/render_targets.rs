@@ -0,0 +1,83 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn clearing_render_targets_allows_a_new_frame() {
+    let (mut rast, _framebuffer) = target(2, 2, false);
+    rast.push_render_target(Arc::new(Mutex::new(Framebuffer::new(2, 2, 1, false))))
+        .unwrap();
+
+    assert!(matches!(
+        rast.new_frame(),
+        Err(RasterizerError::RenderTargetUnfinished)
+    ));
+
+    rast.clear_render_targets();
+    rast.new_frame().unwrap();
+
+    // nothing is left to pop or render into
+    assert!(rast.pop_render_target().is_err());
+}
+
+#[test]
+fn pushing_past_the_depth_limit_overflows() {
+    let framebuffer = Arc::new(Mutex::new(Framebuffer::new(1, 1, 1, false)));
+    let mut rast = Rasterizer::new();
+
+    for _ in 0..DEFAULT_MAX_RENDER_TARGET_DEPTH {
+        rast.push_render_target(framebuffer.clone()).unwrap();
+    }
+
+    assert_eq!(rast.render_target_depth(), DEFAULT_MAX_RENDER_TARGET_DEPTH);
+    assert!(matches!(
+        rast.push_render_target(framebuffer.clone()),
+        Err(RasterizerError::RenderTargetStackOverflow)
+    ));
+
+    // the failed push doesn't grow the stack
+    assert_eq!(rast.render_target_depth(), DEFAULT_MAX_RENDER_TARGET_DEPTH);
+
+    rast.clear_render_targets();
+    rast.set_max_render_target_depth(2);
+
+    rast.push_render_target(framebuffer.clone()).unwrap();
+    rast.push_render_target(framebuffer.clone()).unwrap();
+    assert!(matches!(
+        rast.push_render_target(framebuffer.clone()),
+        Err(RasterizerError::RenderTargetStackOverflow)
+    ));
+
+    assert_eq!(rast.render_target_depth(), 2);
+}
+
+// renders into a borrowed framebuffer while another one is pushed, which must not be touched
+#[test]
+fn render_indexed_into_bypasses_the_render_target() {
+    let (mut rast, pushed) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let mut framebuffer = cleared_framebuffer(4, 4, false);
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed_into(&call, &mut framebuffer).unwrap();
+
+    let drawn = framebuffer.color_attachments()[0].data();
+    assert!(drawn.iter().all(|color| *color == 0xFF0000FF));
+    assert!(
+        color_data(&pushed)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+
+    // the stack is left exactly as it was
+    assert_eq!(rast.render_target_depth(), 1);
+    assert!(Arc::ptr_eq(&rast.current_render_target().unwrap(), &pushed));
+}
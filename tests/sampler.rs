@@ -0,0 +1,74 @@
+use nalgebra::{Point2, Vector2};
+
+use rast::graphics::*;
+
+// an 8x8 mip chain where every texel holds the index of its level
+fn numbered_levels() -> Vec<Image<f32>> {
+    (0..4)
+        .map(|level| {
+            let size = 8 >> level;
+            let mut image = Image::new(size, size);
+            image.data_mut().fill(level as f32);
+            image
+        })
+        .collect()
+}
+
+fn sampled_level(lod_bias: f32, texels_per_pixel: f32) -> f32 {
+    let sampler = SamplerState {
+        filter: Filter::Nearest,
+        lod_bias,
+        ..Default::default()
+    };
+
+    let derivative = texels_per_pixel / 8.0;
+    sample_mip_chain(
+        &numbered_levels(),
+        &sampler,
+        Point2::new(0.5, 0.5),
+        Vector2::new(derivative, 0.0),
+        Vector2::new(0.0, derivative),
+    )
+}
+
+#[test]
+fn lod_bias_shifts_the_selected_mip() {
+    // one texel per pixel samples the full size image
+    assert_eq!(sampled_level(0.0, 1.0), 0.0);
+
+    assert_eq!(sampled_level(1.0, 1.0), 1.0);
+    assert_eq!(sampled_level(1.4, 1.0), 1.0);
+    assert_eq!(sampled_level(1.6, 1.0), 2.0);
+    assert_eq!(sampled_level(-1.0, 2.0), 0.0);
+
+    // biases past either end of the chain stick to the first or last level
+    assert_eq!(sampled_level(10.0, 1.0), 3.0);
+    assert_eq!(sampled_level(-10.0, 4.0), 0.0);
+}
+
+// a quarter of the image past either edge, on a 4x1 image holding each texel's index. nearest
+// filtering picks texel -1 on the left and texel 5 on the right before they're wrapped
+#[test]
+fn wrap_modes_fold_uvs_outside_of_the_image() {
+    let mut image = Image::new(4, 1);
+    image.data_mut().copy_from_slice(&[0.0f32, 1.0, 2.0, 3.0]);
+
+    for (wrap, left, right) in [
+        (WrapMode::Repeat, 3.0, 1.0),
+        (WrapMode::MirroredRepeat, 0.0, 2.0),
+        (WrapMode::ClampToEdge, 0.0, 3.0),
+    ] {
+        let sampler = SamplerState {
+            filter: Filter::Nearest,
+            wrap_u: wrap,
+            ..Default::default()
+        };
+
+        let sample = |u: f32| sample_image(&image, &sampler, Point2::new(u, 0.5));
+        assert_eq!(sample(-0.25), left, "{wrap:?}");
+        assert_eq!(sample(1.25), right, "{wrap:?}");
+
+        // inside of the image every mode agrees
+        assert_eq!(sample(0.6), 2.0, "{wrap:?}");
+    }
+}
@@ -0,0 +1,279 @@
+mod common;
+
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn touched_scissor_bounds_every_written_pixel() {
+    let (mut rast, framebuffer) = target(64, 64, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.5, -0.5, 0.5),
+            Point3::new(0.0, -0.5, 0.5),
+            Point3::new(-0.5, 0.0, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    let touched = rast.render_indexed_touched(&call).unwrap();
+
+    let colors = color_data(&framebuffer);
+    let written: Vec<_> = (0..colors.len())
+        .filter(|i| colors[*i] != CLEAR_COLOR)
+        .map(|i| (i % 64, i / 64))
+        .collect();
+
+    assert!(!written.is_empty());
+    assert!(written.iter().all(|(x, y)| touched.contains(*x, *y)));
+
+    // conservative, but not by more than a pixel on either side
+    let extent = |coordinate: fn(&(usize, usize)) -> usize| {
+        let values = written.iter().map(coordinate);
+        values.clone().max().unwrap() - values.min().unwrap() + 1
+    };
+
+    let (width, height) = (extent(|p| p.0), extent(|p| p.1));
+    assert!(
+        touched.width <= width + 2 && touched.height <= height + 2,
+        "{touched:?}"
+    );
+}
+
+#[test]
+fn touched_scissor_is_empty_when_nothing_is_drawn() {
+    let (mut rast, _framebuffer) = target(64, 64, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[], &uniforms).build();
+    let touched = rast.render_indexed_touched(&call).unwrap();
+
+    assert_eq!((touched.width, touched.height), (0, 0));
+}
+
+// renders one triangle, returning the rows it covered and the rows of the scissor generated for it
+fn generated_and_covered_rows(
+    vertices: Vec<Point3<f32>>,
+) -> (RangeInclusive<usize>, RangeInclusive<usize>) {
+    let (mut rast, framebuffer) = target(37, 23, false);
+
+    let generated = Arc::new(Mutex::new(None));
+    let observed = generated.clone();
+    rast.set_face_observer(Some(Box::new(move |info: &FaceDebugInfo| {
+        *observed.lock().unwrap() = info.scissor.cloned();
+    })));
+
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices,
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+
+    let scissor = generated.lock().unwrap().clone().unwrap();
+    let data = color_data(&framebuffer);
+    let covered: Vec<_> = (0..23)
+        .filter(|y| data[y * 37..(y + 1) * 37].contains(&0xFFFFFFFF))
+        .collect();
+
+    (
+        scissor.y..=scissor.y + scissor.height - 1,
+        covered[0]..=covered[covered.len() - 1],
+    )
+}
+
+#[test]
+fn generated_scissor_matches_the_covered_rows() {
+    let triangles = [
+        // in the top half, pointing down
+        [(-0.8, -0.9), (0.6, -0.7), (0.1, -0.1)],
+        // in the bottom half, pointing up
+        [(0.2, 0.3), (0.9, 0.95), (-0.7, 0.8)],
+        // spanning the middle, hanging off the bottom edge
+        [(-0.3, -0.2), (0.7, 1.4), (-0.9, 0.4)],
+    ];
+
+    for triangle in triangles {
+        let vertices = triangle
+            .iter()
+            .map(|(x, y)| Point3::new(*x, *y, 0.5))
+            .collect();
+
+        let (generated, covered) = generated_and_covered_rows(vertices);
+        assert_eq!(generated, covered, "{triangle:?}");
+    }
+}
+
+#[test]
+fn pushed_scissor_bounds_clears_and_draws() {
+    let (mut rast, framebuffer) = target(8, 8, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let inside = |x: usize, y: usize| (2..6).contains(&x) && (2..6).contains(&y);
+    let assert_inside = |inside_color: u32| {
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = match inside(x, y) {
+                    true => inside_color,
+                    false => CLEAR_COLOR,
+                };
+
+                assert_eq!(pixel(&framebuffer, x, y), expected, "pixel ({x}, {y})");
+            }
+        }
+    };
+
+    rast.push_scissor(Scissor {
+        x: 2,
+        y: 2,
+        width: 4,
+        height: 4,
+    });
+
+    rast.clear(&ClearValue {
+        color: 0x222222FF,
+        depth: 1.0,
+    })
+    .unwrap();
+
+    assert_inside(0x222222FF);
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    rast.render_indexed(&call).unwrap();
+    assert_inside(0xFF0000FF);
+
+    rast.pop_scissor().unwrap();
+    assert!(rast.current_scissor().is_none());
+}
+
+#[test]
+fn zero_height_scissors_draw_nothing() {
+    let (mut rast, framebuffer) = target(4, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+
+    // a sliver between the centers of rows 1 and 2, so its bounds hold no row at all
+    let sliver = FlatUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -0.1, 0.5),
+            Point3::new(1.0, -0.1, 0.5),
+            Point3::new(0.0, 0.1, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &sliver).build();
+    rast.render_indexed(&call).unwrap();
+    rast.render_indexed_tiled(&call, 1).unwrap();
+
+    let stats = rast.stats();
+    assert_eq!(stats.faces_processed, 2);
+    assert_eq!(stats.faces_empty, 2);
+    assert_eq!(stats.faces_rendered, 0);
+
+    // an empty user scissor is fine too
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .scissor(Scissor {
+            x: 0,
+            y: 2,
+            width: 4,
+            height: 0,
+        })
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+    rast.render_indexed_tiled(&call, 1).unwrap();
+
+    assert!(
+        color_data(&framebuffer)
+            .iter()
+            .all(|color| *color == CLEAR_COLOR)
+    );
+    assert_eq!(rast.stats().faces_rendered, 0);
+}
+
+fn bounds(scissor: &Scissor) -> (usize, usize, usize, usize) {
+    (scissor.x, scissor.y, scissor.width, scissor.height)
+}
+
+#[test]
+fn scissors_scale_and_offset() {
+    let scissor = Scissor {
+        x: 3,
+        y: 5,
+        width: 10,
+        height: 10,
+    };
+
+    assert_eq!(bounds(&scissor.scaled(2)), (6, 10, 20, 20));
+    assert_eq!(bounds(&scissor.scaled(1)), bounds(&scissor));
+
+    let moved = scissor.offset_by(-3, 2).unwrap();
+    assert_eq!(bounds(&moved), (0, 7, 10, 10));
+
+    // one past the origin on either axis
+    assert!(scissor.offset_by(-4, 0).is_none());
+    assert!(scissor.offset_by(0, -6).is_none());
+}
+
+// draws a small triangle with a user scissor that already bounds it, using the scissor as the
+// only bound or intersecting it with the generated one
+fn render_bounded(user_scissor_only: bool, tile_height: Option<usize>) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(32, 32, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.6, -0.7, 0.5),
+            Point3::new(0.1, -0.5, 0.5),
+            Point3::new(-0.4, 0.2, 0.5),
+        ],
+        color: 0xFF0000FF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .scissor(Scissor {
+            x: 4,
+            y: 3,
+            width: 16,
+            height: 18,
+        })
+        .use_user_scissor_only(user_scissor_only)
+        .build();
+
+    match tile_height {
+        Some(tile_height) => rast.render_indexed_tiled(&call, tile_height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn user_scissor_only_matches_the_generated_scissor() {
+    let intersected = render_bounded(false, None);
+    assert!(intersected.contains(&0xFF0000FF));
+
+    assert!(render_bounded(true, None) == intersected);
+    assert!(render_bounded(true, Some(5)) == intersected);
+}
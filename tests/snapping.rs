@@ -0,0 +1,49 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+// a triangle whose left edge is nudged by offset from x = 10.5 pixels, right on a column of pixel
+// centers
+fn render_left_edge(
+    offset: f32,
+    subpixel_bits: Option<u8>,
+    tile_height: Option<usize>,
+) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(32, 32, false);
+    rast.set_subpixel_bits(subpixel_bits);
+
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let x = 10.5 / 16.0 - 1.0 + offset;
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(x, -0.9, 0.5),
+            Point3::new(0.9, -0.9, 0.5),
+            Point3::new(x, 0.9, 0.5),
+        ],
+        color: 0xFFFFFFFF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    match tile_height {
+        Some(tile_height) => rast.render_indexed_tiled(&call, tile_height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn snapping_hides_tiny_vertex_jitter() {
+    // unsnapped, the column of centers flips in and out with the edge
+    assert!(render_left_edge(1e-5, None, None) != render_left_edge(-1e-5, None, None));
+
+    let snapped = render_left_edge(0.0, Some(8), None);
+    for offset in [1e-5, -1e-5] {
+        assert!(render_left_edge(offset, Some(8), None) == snapped);
+        assert!(render_left_edge(offset, Some(8), Some(4)) == snapped);
+    }
+}
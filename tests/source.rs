@@ -0,0 +1,109 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+// a SourceShader over MeshData has to draw exactly what the hand written InstanceShader does with
+// the same vertices, offsets and colors
+#[test]
+fn source_shader_matches_a_hand_written_shader() {
+    let vertices = vec![
+        Point3::new(-0.9, -0.9, 0.5),
+        Point3::new(0.5, -0.7, 0.5),
+        Point3::new(-0.8, 0.9, 0.5),
+    ];
+
+    let colors = vec![0xFF0000FF, 0x00FF00FF, 0x0000FFFF];
+    let offset = 0.3;
+
+    let (mut rast, framebuffer) = target(32, 24, false);
+    let pipeline = Pipeline::builder(InstanceShader {}).build().unwrap();
+    let uniforms = InstanceUniforms {
+        vertices: vertices.clone(),
+        colors: colors.clone(),
+        offset,
+    };
+
+    rast.render_indexed(
+        &IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+            .instances(colors.len())
+            .build(),
+    )
+    .unwrap();
+
+    let expected = color_data(&framebuffer);
+
+    // every instance is an x offset and a color
+    let shader = SourceShader::new(
+        |vertex: &Point3<f32>, instance: &(f32, u32)| VertexOutput {
+            position: Point3::new(vertex.x + instance.0, vertex.y, vertex.z),
+            data: 0.0f32,
+        },
+        |_working: &f32, instance: &(f32, u32)| instance.1,
+    );
+
+    let mesh = MeshData {
+        vertices,
+        instances: Vec::from_iter(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, color)| (i as f32 * offset, *color)),
+        ),
+    };
+
+    let (mut rast, framebuffer) = target(32, 24, false);
+    let pipeline = Pipeline::builder(shader).build().unwrap();
+
+    rast.render_indexed(
+        &IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &mesh)
+            .instances(mesh.instances.len())
+            .build(),
+    )
+    .unwrap();
+
+    let rendered = color_data(&framebuffer);
+    for color in colors {
+        assert!(rendered.contains(&color), "{color:08X} never drawn");
+    }
+
+    assert!(rendered == expected);
+}
+
+struct Vertex {
+    position: Point3<f32>,
+    color: u32,
+}
+
+#[test]
+fn vertex_buffers_bounds_check_fetches() {
+    let buffer = VertexBuffer::new(vec![
+        Vertex {
+            position: Point3::new(0.0, 1.0, 2.0),
+            color: 0xFF0000FF,
+        },
+        Vertex {
+            position: Point3::new(3.0, 4.0, 5.0),
+            color: 0x00FF00FF,
+        },
+    ]);
+
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(buffer.get(1).unwrap().color, 0x00FF00FF);
+    assert_eq!(
+        buffer.attribute(0, |vertex| vertex.position).unwrap(),
+        Point3::new(0.0, 1.0, 2.0)
+    );
+
+    assert!(matches!(
+        buffer.get(2),
+        Err(VertexBufferError::OutOfRange { index: 2, len: 2 })
+    ));
+    assert!(matches!(
+        buffer.attribute(7, |vertex| vertex.color),
+        Err(VertexBufferError::OutOfRange { index: 7, len: 2 })
+    ));
+}
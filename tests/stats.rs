@@ -0,0 +1,56 @@
+use std::sync::Arc;
+use std::thread;
+
+use rast::graphics::*;
+
+#[test]
+fn stats_merged_from_many_threads_add_up() {
+    let stats = Arc::new(AtomicRenderStats::default());
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let stats = stats.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    stats.merge(&RenderStats {
+                        calls: 1,
+                        faces_processed: 2,
+                        ..Default::default()
+                    });
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.calls, 8000);
+    assert_eq!(snapshot.faces_processed, 16000);
+}
+
+// the keys are the field names, in declaration order
+#[cfg(feature = "serde")]
+#[test]
+fn stats_serialize_to_their_field_names() {
+    let stats = RenderStats {
+        faces_processed: 1,
+        faces_rendered: 2,
+        faces_culled_small: 3,
+        faces_empty: 4,
+        instances: 5,
+        instances_culled: 6,
+        viewport_instances: 7,
+        calls: 8,
+    };
+
+    assert_eq!(
+        serde_json::to_string(&stats).unwrap(),
+        concat!(
+            r#"{"faces_processed":1,"faces_rendered":2,"faces_culled_small":3,"faces_empty":4,"#,
+            r#""instances":5,"instances_culled":6,"viewport_instances":7,"calls":8}"#
+        )
+    );
+}
@@ -0,0 +1,238 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+fn overlapping_triangles(colors: Vec<u32>) -> InstanceUniforms {
+    InstanceUniforms {
+        vertices: vec![
+            Point3::new(-0.9, -0.9, 0.5),
+            Point3::new(0.5, -0.7, 0.5),
+            Point3::new(-0.8, 0.9, 0.5),
+        ],
+        colors,
+        offset: 0.3,
+    }
+}
+
+// renders every instance of uniforms with src-over blending, either tiled or through
+// render_indexed
+fn render_translucent(uniforms: &InstanceUniforms, tile_height: Option<usize>) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(37, 29, false);
+    let pipeline = Pipeline::builder(InstanceShader {})
+        .blending(vec![src_over()])
+        .color_attachments(1)
+        .build()
+        .unwrap();
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], uniforms)
+        .instances(uniforms.colors.len())
+        .build();
+
+    match tile_height {
+        Some(tile_height) => rast.render_indexed_tiled(&call, tile_height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    color_data(&framebuffer)
+}
+
+#[test]
+fn tiled_translucent_overlap_matches_serial() {
+    let uniforms = overlapping_triangles(vec![0xFF000080, 0x00FF0080]);
+    let serial = render_translucent(&uniforms, None);
+
+    // tiles that don't divide the height evenly, and one taller than the framebuffer
+    for tile_height in [1, 4, 7, 64] {
+        assert!(render_translucent(&uniforms, Some(tile_height)) == serial);
+    }
+}
+
+// the output is rendered one tile at a time, so the tiles put back together have to match
+// rendering all of it at once
+#[test]
+fn tiles_reassemble_into_the_full_render() {
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.9, -0.8, 0.5),
+            Point3::new(0.95, -0.3, 0.5),
+            Point3::new(-0.2, 0.9, 0.5),
+            Point3::new(0.7, 0.8, 0.5),
+        ],
+        color: 0xFF0000FF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 1, 3, 2], &uniforms).build();
+    let clear = ClearValue {
+        color: CLEAR_COLOR,
+        depth: 1.0,
+    };
+
+    let (mut rast, framebuffer) = target(40, 30, true);
+    rast.render_indexed(&call).unwrap();
+    rast.pop_render_target().unwrap();
+    let full = color_data(&framebuffer);
+
+    // 2x2 tiles, then tiles that don't divide the output evenly
+    for (tile_size, tile_count) in [((20, 15), 4), ((13, 11), 12)] {
+        let mut reassembled = vec![0; 40 * 30];
+        let mut tiles = 0;
+
+        rast.render_tiled_to((40, 30), tile_size, &call, &clear, |tile, image| {
+            assert_eq!(image.size(), (tile.width, tile.height));
+            for (x, y) in image.coordinates() {
+                reassembled[(tile.y + y) * 40 + tile.x + x] = *image.at(x, y).unwrap();
+            }
+
+            tiles += 1;
+        })
+        .unwrap();
+
+        assert_eq!(tiles, tile_count);
+        assert!(reassembled == full);
+    }
+
+    // every tile is pushed and popped as a render target of its own
+    assert_eq!(rast.render_target_depth(), 0);
+}
+
+// blending isn't commutative, so swapping the submission order of two overlapping translucent
+// faces changes the result. tiling must not lose that order in either direction
+#[test]
+fn tiled_overlap_keeps_submission_order() {
+    let red_then_green = overlapping_triangles(vec![0xFF000080, 0x00FF0080]);
+    let green_then_red = overlapping_triangles(vec![0x00FF0080, 0xFF000080]);
+
+    let serial_red_then_green = render_translucent(&red_then_green, None);
+    let serial_green_then_red = render_translucent(&green_then_red, None);
+    assert!(serial_red_then_green != serial_green_then_red);
+
+    for tile_height in [1, 4, 7, 64] {
+        assert!(render_translucent(&red_then_green, Some(tile_height)) == serial_red_then_green);
+        assert!(render_translucent(&green_then_red, Some(tile_height)) == serial_green_then_red);
+    }
+}
+
+// the tiles take their attachments and formats from the current render target, so a pipeline
+// blending into two attachments of packed colors works the same tiled as it does directly
+#[test]
+fn tiles_match_the_current_render_target() {
+    let pipeline = Pipeline::builder(FlatShader {})
+        .blending(vec![Some(src_over()), None])
+        .color_attachments(2)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-0.9, -0.8, 0.5),
+            Point3::new(0.95, -0.3, 0.5),
+            Point3::new(-0.2, 0.9, 0.5),
+        ],
+        color: 0xFF000080,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    let clear = ClearValue {
+        color: 0x0000FFFF,
+        depth: 1.0,
+    };
+
+    let mut framebuffer = Framebuffer::new(16, 12, 2, false);
+    framebuffer.set_color_format(PixelFormat::Rgb10A2);
+    framebuffer.clear(&clear).unwrap();
+
+    let framebuffer = Arc::new(Mutex::new(framebuffer));
+    let mut rast = Rasterizer::new();
+    rast.push_render_target(framebuffer.clone()).unwrap();
+    rast.render_indexed(&call).unwrap();
+
+    let full = color_data(&framebuffer);
+    let mut reassembled = vec![0; 16 * 12];
+
+    rast.render_tiled_to((16, 12), (8, 6), &call, &clear, |tile, image| {
+        for (x, y) in image.coordinates() {
+            reassembled[(tile.y + y) * 16 + tile.x + x] = *image.at(x, y).unwrap();
+        }
+    })
+    .unwrap();
+
+    assert!(reassembled == full);
+
+    // the caller's render target is left where it was
+    assert_eq!(rast.render_target_depth(), 1);
+}
+
+// colors each face of a single instance by its position in the index buffer, so two faces of the
+// same draw blend differently depending on which one comes first
+struct FaceColorShader {}
+
+struct FaceColorUniforms {
+    vertices: Vec<Point3<f32>>,
+    colors: Vec<u32>,
+}
+
+impl Shader for FaceColorShader {
+    type Uniform = FaceColorUniforms;
+    type Working = f32;
+
+    fn vertex_stage(&self, context: &VertexContext<Self::Uniform>) -> VertexOutput<Self::Working> {
+        VertexOutput {
+            position: context.data.vertices[context.vertex_id],
+            data: (context.vertex_id / 3) as f32,
+        }
+    }
+
+    fn fragment_stage(&self, context: &FragmentContext<Self::Uniform, Self::Working>) -> u32 {
+        context.data.colors[context.working.round() as usize]
+    }
+}
+
+fn render_overlapping_faces(indices: &[u16], tile_height: Option<usize>) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(16, 12, false);
+    let pipeline = Pipeline::builder(FaceColorShader {})
+        .blending(vec![src_over()])
+        .build()
+        .unwrap();
+
+    let uniforms = FaceColorUniforms {
+        vertices: vec![
+            Point3::new(-0.9, -0.9, 0.5),
+            Point3::new(0.5, -0.7, 0.5),
+            Point3::new(-0.8, 0.9, 0.5),
+            Point3::new(-0.4, -0.6, 0.5),
+            Point3::new(0.9, -0.2, 0.5),
+            Point3::new(0.1, 0.8, 0.5),
+        ],
+        colors: vec![0xFF000080, 0x00FF0080],
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, indices, &uniforms).build();
+    match tile_height {
+        Some(tile_height) => rast.render_indexed_tiled(&call, tile_height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    color_data(&framebuffer)
+}
+
+// both faces land in the one tile taller than the framebuffer, where they have to be walked in the
+// order they were submitted in
+#[test]
+fn overlapping_faces_in_one_tile_keep_submission_order() {
+    let red_then_green = [0, 1, 2, 3, 4, 5];
+    let green_then_red = [3, 4, 5, 0, 1, 2];
+
+    let serial_red_then_green = render_overlapping_faces(&red_then_green, None);
+    let serial_green_then_red = render_overlapping_faces(&green_then_red, None);
+    assert!(serial_red_then_green != serial_green_then_red);
+
+    assert!(render_overlapping_faces(&red_then_green, Some(64)) == serial_red_then_green);
+    assert!(render_overlapping_faces(&green_then_red, Some(64)) == serial_green_then_red);
+}
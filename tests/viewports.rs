@@ -0,0 +1,139 @@
+mod common;
+
+use nalgebra::Point3;
+
+use rast::graphics::*;
+
+use common::*;
+
+#[test]
+fn left_and_right_viewports_are_both_filled() {
+    let (mut rast, framebuffer) = target(8, 4, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+
+    // a quad over the left half of NDC, so each viewport only fills its own left half
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(-1.0, -1.0, 0.5),
+            Point3::new(0.0, -1.0, 0.5),
+            Point3::new(0.0, 1.0, 0.5),
+            Point3::new(-1.0, 1.0, 0.5),
+        ],
+        color: 0xFF0000FF,
+    };
+
+    let viewports = [
+        Viewport::full(4, 4),
+        Viewport {
+            x: 4,
+            ..Viewport::full(4, 4)
+        },
+    ];
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2, 0, 2, 3], &uniforms)
+        .viewports(&viewports)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+
+    for y in 0..4 {
+        for x in 0..8 {
+            let expected = match x % 4 < 2 {
+                true => 0xFF0000FF,
+                false => CLEAR_COLOR,
+            };
+
+            assert_eq!(pixel(&framebuffer, x, y), expected, "pixel ({x}, {y})");
+        }
+    }
+
+    assert_eq!(rast.stats().viewport_instances, 2);
+}
+
+fn render_into_quadrant(viewports: &[Viewport]) -> Vec<u32> {
+    let (mut rast, framebuffer) = target(16, 16, false);
+    let pipeline = Pipeline::builder(FlatShader {}).build().unwrap();
+    let uniforms = FlatUniforms {
+        vertices: fullscreen(0.5),
+        color: 0xFF0000FF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms)
+        .render_area(Scissor {
+            x: 8,
+            y: 8,
+            width: 8,
+            height: 8,
+        })
+        .viewports(viewports)
+        .build();
+
+    rast.render_indexed(&call).unwrap();
+    color_data(&framebuffer)
+}
+
+#[test]
+fn render_area_confines_the_call() {
+    // the default viewport covers just the render area, while an explicit one covering the whole
+    // framebuffer is clipped to it
+    for viewports in [&[][..], &[Viewport::full(16, 16)][..]] {
+        let data = render_into_quadrant(viewports);
+        for y in 0..16 {
+            for x in 0..16 {
+                let expected = match x >= 8 && y >= 8 {
+                    true => 0xFF0000FF,
+                    false => CLEAR_COLOR,
+                };
+
+                assert_eq!(data[y * 16 + x], expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+}
+
+// covered pixels per row of a triangle with its apex near the top of NDC
+fn row_coverage(origin: Origin, tile_height: Option<usize>) -> Vec<usize> {
+    let (mut rast, framebuffer) = target(16, 16, false);
+    rast.set_origin(origin);
+
+    // culling checks that the flip keeps the winding intact
+    let pipeline = Pipeline::builder(FlatShader {})
+        .cull_back(true)
+        .build()
+        .unwrap();
+
+    let uniforms = FlatUniforms {
+        vertices: vec![
+            Point3::new(0.0, -0.8, 0.5),
+            Point3::new(0.8, 0.8, 0.5),
+            Point3::new(-0.8, 0.8, 0.5),
+        ],
+        color: 0xFF0000FF,
+    };
+
+    let call = IndexedRenderCall::builder(&pipeline, &[0, 1, 2], &uniforms).build();
+    match tile_height {
+        Some(tile_height) => rast.render_indexed_tiled(&call, tile_height).unwrap(),
+        None => rast.render_indexed(&call).unwrap(),
+    }
+
+    let data = color_data(&framebuffer);
+    data.chunks_exact(16)
+        .map(|row| row.iter().filter(|color| **color != CLEAR_COLOR).count())
+        .collect()
+}
+
+#[test]
+fn bottom_left_origin_mirrors_vertically() {
+    for tile_height in [None, Some(3)] {
+        // the apex is at the top with the default origin, and at the bottom when flipped
+        let top_left = row_coverage(Origin::TopLeft, tile_height);
+        assert!(top_left[3] < top_left[12], "{top_left:?}");
+
+        let mut bottom_left = row_coverage(Origin::BottomLeft, tile_height);
+        assert!(bottom_left[3] > bottom_left[12], "{bottom_left:?}");
+
+        bottom_left.reverse();
+        assert_eq!(top_left, bottom_left);
+    }
+}